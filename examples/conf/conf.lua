@@ -0,0 +1,13 @@
+-- Runs before the window is created. Only table, string and math are available here.
+function husky.conf(t)
+	t.window.title = "Configured window"
+	t.window.width = 800
+	t.window.height = 600
+	t.window.minwidth = 400
+	t.window.minheight = 300
+	t.window.vsync = true
+	t.window.msaa = 4
+
+	-- This example doesn't need voxels
+	t.modules.voxel = false
+end
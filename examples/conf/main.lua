@@ -0,0 +1,4 @@
+function husky.draw()
+	husky.graphics:clear(0.2, 0.3, 0.5)
+	husky.graphics:print("voxel module loaded: " .. tostring(husky.voxel ~= nil), 0, 0)
+end
@@ -0,0 +1,4 @@
+function husky.conf(t)
+	t.timestep.fixedrate = 30
+	t.timestep.maxsteps = 5
+end
@@ -0,0 +1,20 @@
+-- The box moves in fixed steps of 1/30th of a second, but is drawn smoothly
+-- by interpolating between the last two steps.
+local x, previous_x = 0, 0
+local speed = 400
+
+function husky.fixedupdate(dt)
+	previous_x = x
+	x = x + speed * dt
+
+	local width = husky.graphics:getSize()
+	if x > width - 100 or x < 0 then
+		speed = -speed
+	end
+end
+
+function husky.draw(alpha)
+	husky.graphics:clear(0.1, 0.1, 0.15)
+	local drawn_x = previous_x + (x - previous_x) * alpha
+	husky.graphics:rect("fill", drawn_x, 100, 100, 100)
+end
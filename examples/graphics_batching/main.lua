@@ -0,0 +1,19 @@
+-- Thousands of rectangles, which end up in a handful of draw calls.
+local stats = { drawcalls = 0, vertices = 0 }
+
+function husky.draw()
+	husky.graphics:clear(0.1, 0.1, 0.1)
+
+	local w, h = husky.graphics:getSize()
+	for x = 0, w, 16 do
+		for y = 0, h, 16 do
+			husky.graphics:setColor(x / w, y / h, 0.5)
+			husky.graphics:rect("fill", x, y, 14, 14)
+		end
+	end
+
+	-- getStats reports on the previous frame
+	stats = husky.graphics:getStats()
+	husky.graphics:setColor(1, 1, 1)
+	husky.graphics:print("draw calls: " .. stats.drawcalls .. ", vertices: " .. stats.vertices, 10, 10)
+end
@@ -0,0 +1,29 @@
+local modes = { "alpha", "add", "subtract", "screen", "replace" }
+
+function husky.draw()
+	husky.graphics:clear(0.25, 0.25, 0.3)
+
+	for i, mode in ipairs(modes) do
+		local x = 40 + (i - 1) * 240
+		husky.graphics:setBlendMode(mode)
+		husky.graphics:setColor(1, 0.3, 0.2, 0.6)
+		husky.graphics:circle("fill", x + 70, 200, 70)
+		husky.graphics:setColor(0.2, 0.5, 1, 0.6)
+		husky.graphics:circle("fill", x + 130, 200, 70)
+	end
+	husky.graphics:setBlendMode("alpha")
+
+	--Only the middle of these stripes gets drawn
+	husky.graphics:setScissor(200, 380, 800, 200)
+	husky.graphics:intersectScissor(100, 420, 700, 300)
+	for i = 0, 20 do
+		husky.graphics:setColor(i / 20, 0.8, 1 - i / 20)
+		husky.graphics:rect("fill", i * 60, 360, 40, 300)
+	end
+	husky.graphics:setScissor()
+
+	husky.graphics:setColor(1, 1, 1)
+	for i, mode in ipairs(modes) do
+		husky.graphics:print(mode, 40 + (i - 1) * 240, 40)
+	end
+end
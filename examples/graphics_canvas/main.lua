@@ -0,0 +1,25 @@
+--Draws a tiny scene into a 160x90 canvas, then scales it up to fill the window
+local canvas = husky.graphics:newCanvas(160, 90)
+canvas:setFilter("nearest")
+
+local time = 0
+
+function husky.update(dt)
+	time = time + dt
+end
+
+function husky.draw()
+	husky.graphics:setCanvas(canvas)
+	husky.graphics:clear(0.1, 0.1, 0.2)
+	husky.graphics:setColor(0.9, 0.7, 0.2)
+	husky.graphics:circle("fill", 80 + math.cos(time) * 50, 45 + math.sin(time) * 25, 10)
+	husky.graphics:setColor(0.3, 0.8, 0.4)
+	husky.graphics:rect("fill", 0, 75, 160, 15)
+	husky.graphics:setCanvas()
+
+	husky.graphics:clear(0, 0, 0)
+	local winSizeX, winSizeY = husky.graphics:getSize()
+	local scale = math.min(winSizeX / 160, winSizeY / 90)
+	husky.graphics:setColor(1, 1, 1)
+	husky.graphics:draw(canvas, winSizeX / 2, winSizeY / 2, 0, scale, scale, 80, 45)
+end
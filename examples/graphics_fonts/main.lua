@@ -0,0 +1,22 @@
+--newFont("fonts/MyFont.ttf", 32) loads a TTF or OTF file from the game, a size on its own uses the built in font
+local small = husky.graphics:newFont(14);
+local big = husky.graphics:newFont(48);
+local spaced = husky.graphics:newFont(20);
+spaced:setLineHeight(1.8);
+
+function husky.draw()
+	husky.graphics:clear(0.15, 0.15, 0.2);
+
+	husky.graphics:setFont(big);
+	local text = "Big text";
+	husky.graphics:print(text, 20, 20);
+	--Underline the text using the font metrics
+	local baseline = 20 + big:getAscent();
+	husky.graphics:line(20, baseline + 4, 20 + big:getWidth(text), baseline + 4);
+
+	husky.graphics:setFont(small);
+	husky.graphics:print("ascent " .. big:getAscent() .. ", descent " .. big:getDescent() .. ", height " .. big:getHeight(), 20, 90);
+
+	husky.graphics:setFont(spaced);
+	husky.graphics:print("Lines with\nmore space\nbetween them", 20, 130);
+end
@@ -0,0 +1,21 @@
+local smiley = husky.graphics:newImage("smiley.png")
+-- Keep the pixels sharp when scaling up
+smiley:setFilter("nearest")
+
+local angle = 0
+
+function husky.update(dt)
+	angle = angle + dt
+end
+
+function husky.draw()
+	husky.graphics:clear(0.2, 0.25, 0.3)
+
+	local w, h = smiley:getDimensions()
+	husky.graphics:setColor(1, 1, 1)
+	husky.graphics:draw(smiley, 50, 50)
+	husky.graphics:draw(smiley, 200, 200, angle, 8, 8, w / 2, h / 2)
+
+	husky.graphics:setColor(0.5, 1, 0.5)
+	husky.graphics:draw(smiley, 400, 50, 0, 4)
+end
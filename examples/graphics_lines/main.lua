@@ -0,0 +1,24 @@
+local joins = { "miter", "bevel", "none" }
+
+function husky.draw()
+	husky.graphics:clear(0.1, 0.1, 0.15)
+
+	husky.graphics:setLineWidth(12)
+	for i, join in ipairs(joins) do
+		local x = 60 + (i - 1) * 220
+		husky.graphics:setLineJoin(join)
+
+		husky.graphics:setLineStyle("smooth")
+		husky.graphics:setColor(0.9, 0.6, 0.3)
+		husky.graphics:rect("line", x, 80, 160, 100)
+		husky.graphics:setColor(0.3, 0.8, 0.6)
+		husky.graphics:tri("line", x + 80, 320, 140, 120)
+
+		husky.graphics:setLineStyle("rough")
+		husky.graphics:setColor(0.6, 0.5, 0.9)
+		husky.graphics:circle("line", x + 80, 520, 70)
+
+		husky.graphics:setColor(1, 1, 1)
+		husky.graphics:print(join, x, 30)
+	end
+end
@@ -1,6 +1,18 @@
+local time = 0;
+
+function husky.update(dt)
+	time = time + dt;
+end
+
 function husky.draw()
 	husky.graphics:clear(0.5, 0.2, 0.35);
 	for i=0,40 do
 		husky.graphics:print("text print " .. tostring(i), 0, i * 20);
 	end
+
+	--Text can be scaled, rotated and go partly off screen
+	husky.graphics:print("rotating text", 400, 200, time, 1, 1, 70, 12);
+	husky.graphics:print("big text", 300, 350, 0, 3, 3);
+	husky.graphics:print("this starts off screen", -60, 500);
+	husky.graphics:print("multiple\nlines\nof text", 700, 100);
 end
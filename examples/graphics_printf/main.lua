@@ -0,0 +1,25 @@
+local text = "Text printed with printf wraps at the limit, breaking lines at spaces where it can. Newlines\nstart a new paragraph.";
+local aligns = { "left", "center", "right", "justify" };
+local font = husky.graphics:newFont(16);
+
+function husky.draw()
+	husky.graphics:clear(0.15, 0.15, 0.2);
+	husky.graphics:setFont(font);
+
+	for i, align in ipairs(aligns) do
+		local x = 20 + (i - 1) * 240;
+		husky.graphics:setColor(0.3, 0.3, 0.4);
+		husky.graphics:rect("line", x, 20, 200, 200);
+		husky.graphics:setColor(1, 1, 1);
+		husky.graphics:printf(text, x, 20, 200, align);
+	end
+
+	--Measure the wrapped text before drawing it, to put a box behind it
+	local width, lines = font:getWrap(text, 300);
+	husky.graphics:setColor(0.3, 0.2, 0.3);
+	husky.graphics:rect("fill", 20, 260, width, #lines * font:getHeight());
+	husky.graphics:setColor(1, 1, 1);
+	husky.graphics:printf(text, 20, 260, 300);
+
+	husky.graphics:print({ {1, 0.3, 0.3}, "Colored ", {0.3, 1, 0.3}, "text ", {0.4, 0.6, 1}, "segments" }, 20, 420);
+end
@@ -0,0 +1,28 @@
+--validateShader checks shader code without creating a shader, newShader raises the same errors
+local code = [[
+#version 450 core
+
+in VS_OUTPUT {
+    vec4 Color;
+    vec2 UV;
+} IN;
+
+layout (location = 0) out vec4 Color;
+
+void main() {
+    Color = IN.Color * brightness;
+}
+]]
+
+local ok, errors = husky.graphics:validateShader(code)
+local font = husky.graphics:newFont(16)
+
+function husky.draw()
+	husky.graphics:clear(0.15, 0.15, 0.2)
+	husky.graphics:setFont(font)
+	if ok then
+		husky.graphics:print("The shader is valid", 20, 20)
+	else
+		husky.graphics:printf(errors, 20, 20, 760)
+	end
+end
@@ -1,4 +1,6 @@
-//This shader flips the red and blue channels, and turns the colour to grayscale based on a float
+//This shader flips the red and blue channels, and turns the colour to grayscale based on a float.
+//Fragment shaders get the colour and UV from the default vertex shader through VS_OUTPUT, the alpha as `Alpha`,
+//and the texture being drawn as `tex`, which is plain white when drawing shapes
 
 #version 450 core
 
@@ -7,12 +9,15 @@ in VS_OUTPUT {
     vec2 UV;
 } IN;
 
-uniform vec4 drawColor;
+in float Alpha;
+
+uniform sampler2D tex;
 uniform float grayness;
 
 layout (location = 0) out vec4 Color;
 
 void main() {
-    float luma = 0.33 * drawColor.b + 0.5 * drawColor.g + 0.16 * drawColor.b;
-    Color = vec4(IN.Color.xyz, 1.0) * mix(drawColor.bgra, vec4(vec3(luma), 1.0), 1.0 - grayness);
+    vec4 color = texture(tex, IN.UV) * vec4(IN.Color, Alpha);
+    float luma = 0.33 * color.b + 0.5 * color.g + 0.16 * color.b;
+    Color = mix(color.bgra, vec4(vec3(luma), color.a), 1.0 - grayness);
 }
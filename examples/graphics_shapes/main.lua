@@ -0,0 +1,36 @@
+local star = {}
+for i = 0, 9 do
+	local angle = i * math.pi / 5 - math.pi / 2
+	local radius = (i % 2 == 0) and 60 or 25
+	table.insert(star, 540 + math.cos(angle) * radius)
+	table.insert(star, 120 + math.sin(angle) * radius)
+end
+
+function husky.draw()
+	husky.graphics:clear(0.12, 0.12, 0.16)
+	husky.graphics:setLineWidth(3)
+
+	for i, mode in ipairs({ "fill", "line" }) do
+		local y = (i - 1) * 220
+
+		husky.graphics:setColor(0.9, 0.5, 0.3)
+		husky.graphics:rect(mode, 40, 60 + y, 140, 100, 20)
+		husky.graphics:setColor(0.3, 0.7, 0.9)
+		husky.graphics:ellipse(mode, 300, 110 + y, 80, 45)
+		husky.graphics:setColor(0.4, 0.9, 0.5)
+		husky.graphics:polygon(mode, star[1], star[2] + y, star[3], star[4] + y, star[5], star[6] + y, star[7], star[8] + y,
+			star[9], star[10] + y, star[11], star[12] + y, star[13], star[14] + y, star[15], star[16] + y, star[17], star[18] + y, star[19], star[20] + y)
+		husky.graphics:setColor(0.9, 0.8, 0.3)
+		husky.graphics:arc(mode, "pie", 720, 110 + y, 60, 0.3, math.pi * 1.7)
+		husky.graphics:setColor(0.8, 0.4, 0.8)
+		husky.graphics:arc(mode, "open", 880, 110 + y, 60, 0, math.pi)
+		husky.graphics:setColor(1, 1, 1)
+		husky.graphics:circle(mode, 1040, 110 + y, 6)
+		husky.graphics:circle(mode, 1120, 110 + y, 50, 6)
+	end
+
+	husky.graphics:setColor(1, 1, 1)
+	husky.graphics:line(40, 560, 200, 620, 360, 540, 520, 640)
+	husky.graphics:setPointSize(6)
+	husky.graphics:points({ 600, 580, 640, 600, 680, 580, 720, 600 })
+end
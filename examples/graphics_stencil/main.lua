@@ -0,0 +1,28 @@
+local time = 0
+
+function husky.update(dt)
+	time = time + dt
+end
+
+local function spotlight()
+	local winSizeX, winSizeY = husky.graphics:getSize()
+	husky.graphics:circle("fill", winSizeX / 2 + math.cos(time) * 250, winSizeY / 2 + math.sin(time * 1.3) * 150, 120)
+end
+
+function husky.draw()
+	husky.graphics:clear(0.05, 0.05, 0.08)
+
+	husky.graphics:stencil(spotlight, "replace", 1)
+	husky.graphics:setStencilTest("greater", 0)
+	--Only shows up inside the spotlight
+	for y = 0, 720, 40 do
+		for x = 0, 1280, 40 do
+			husky.graphics:setColor(x / 1280, y / 720, 0.6)
+			husky.graphics:rect("fill", x + 4, y + 4, 32, 32)
+		end
+	end
+	husky.graphics:setStencilTest()
+
+	husky.graphics:setColor(1, 1, 1)
+	husky.graphics:print("stencil spotlight", 10, 10)
+end
@@ -0,0 +1,28 @@
+local angle = 0
+
+function husky.update(dt)
+	angle = angle + dt
+end
+
+function husky.draw()
+	husky.graphics:clear(0.15, 0.15, 0.2)
+
+	local winSizeX, winSizeY = husky.graphics:getSize()
+
+	husky.graphics:push()
+	husky.graphics:translate(winSizeX / 2, winSizeY / 2)
+	husky.graphics:rotate(angle)
+	for i = 0, 5 do
+		husky.graphics:push()
+		husky.graphics:rotate(i * math.pi / 3)
+		husky.graphics:translate(150, 0)
+		husky.graphics:scale(1 + 0.25 * math.sin(angle * 2 + i))
+		husky.graphics:setColor(0.3 + i * 0.1, 0.6, 0.9 - i * 0.1)
+		husky.graphics:rect("fill", -25, -25, 50, 50)
+		husky.graphics:pop()
+	end
+	husky.graphics:pop()
+
+	husky.graphics:setColor(1, 1, 1)
+	husky.graphics:print("transform stack", 10, 10)
+end
@@ -0,0 +1,23 @@
+local shader = husky.graphics:newShader("uniforms_fs.glsl")
+local smiley = husky.graphics:newImage("smiley.png")
+local time = 0
+
+shader:uniform("tint", {1, 0.6, 0.3})
+shader:uniform("stripes", {1, 0.8, 0.6, 0.4})
+shader:uniform("overlay", smiley)
+
+function husky.update(dt)
+	time = time + dt
+end
+
+function husky.draw()
+	husky.graphics:clear(0.15, 0.15, 0.2)
+
+	--Matrices are given row by row
+	local c, s = math.cos(time), math.sin(time)
+	shader:uniform("rotation", { {c, -s}, {s, c} })
+
+	husky.graphics:setShader(shader)
+	husky.graphics:rect("fill", 100, 100, 300, 300)
+	husky.graphics:setShader()
+end
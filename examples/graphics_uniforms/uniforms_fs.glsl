@@ -0,0 +1,24 @@
+//Shows off the uniform types: a vector, a matrix, an array and an extra texture
+
+#version 450 core
+
+in VS_OUTPUT {
+    vec3 Color;
+    vec2 UV;
+} IN;
+
+in float Alpha;
+
+uniform vec3 tint;
+uniform mat2 rotation;
+uniform float stripes[4];
+uniform sampler2D overlay;
+
+layout (location = 0) out vec4 Color;
+
+void main() {
+    vec2 uv = rotation * (IN.UV - 0.5) + 0.5;
+    float stripe = stripes[int(clamp(IN.UV.x * 4.0, 0.0, 3.0))];
+    vec4 overlay_color = texture(overlay, uv);
+    Color = vec4(mix(IN.Color * tint * stripe, overlay_color.rgb, overlay_color.a), Alpha);
+}
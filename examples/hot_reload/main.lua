@@ -0,0 +1,19 @@
+-- Edit and save this file while it is running, the timer keeps counting through the reload.
+local elapsed = 0
+
+function husky.reload_state()
+	return { elapsed = elapsed }
+end
+
+function husky.restore_state(state)
+	elapsed = state.elapsed
+end
+
+function husky.update(dt)
+	elapsed = elapsed + dt
+end
+
+function husky.draw()
+	husky.graphics:clear(0.5, 0.2, 0.35)
+	husky.graphics:print("running for " .. math.floor(elapsed) .. " seconds", 0, 0)
+end
@@ -0,0 +1,19 @@
+local lastEvent = "none"
+
+function husky.keypressed(key, scancode, isrepeat)
+	lastEvent = "keypressed " .. key .. " " .. tostring(isrepeat)
+	-- Escape still quits, unless this returns true to say the key was handled
+end
+
+function husky.mousepressed(x, y, button)
+	lastEvent = "mousepressed " .. button .. " at " .. x .. ", " .. y
+end
+
+function husky.wheelmoved(x, y)
+	lastEvent = "wheelmoved " .. x .. ", " .. y
+end
+
+function husky.draw()
+	husky.graphics:clear(0.5, 0.2, 0.35)
+	husky.graphics:print("last event: " .. lastEvent, 0, 0)
+end
@@ -0,0 +1,23 @@
+local x, y = 200, 200
+local speed = 250
+local clicks = 0
+
+function husky.update(dt)
+	if husky.keyboard.isDown("w", "up") then y = y - speed * dt end
+	if husky.keyboard.isDown("s", "down") then y = y + speed * dt end
+	if husky.keyboard.isDown("a", "left") then x = x - speed * dt end
+	if husky.keyboard.isDown("d", "right") then x = x + speed * dt end
+
+	if husky.mouse.wasPressed(1) then clicks = clicks + 1 end
+end
+
+function husky.draw()
+	husky.graphics:clear(0.5, 0.2, 0.35)
+
+	husky.graphics:setColor(0.2, 0.8, 0.6)
+	husky.graphics:rect("fill", x, y, 40, 40)
+
+	local mx, my = husky.mouse.getPosition()
+	husky.graphics:setColor(1.0, 1.0, 1.0)
+	husky.graphics:print("mouse: " .. mx .. ", " .. my .. " clicks: " .. clicks, 0, 0)
+end
@@ -0,0 +1,12 @@
+-- Modules are found relative to the game, not the folder husky was started from.
+local player = require("player")
+local shapes = require("shapes") -- loads shapes/init.lua
+
+function husky.update(dt)
+	player.update(dt)
+end
+
+function husky.draw()
+	husky.graphics:clear(0.5, 0.2, 0.35)
+	shapes.square(player.x, player.y, 40)
+end
@@ -0,0 +1,7 @@
+local player = { x = 100, y = 100 }
+
+function player.update(dt)
+	player.x = player.x + 50 * dt
+end
+
+return player
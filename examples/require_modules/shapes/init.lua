@@ -0,0 +1,8 @@
+local shapes = {}
+
+function shapes.square(x, y, size)
+	husky.graphics:setColor(0.2, 0.8, 0.6)
+	husky.graphics:rect("fill", x, y, size, size)
+end
+
+return shapes
@@ -0,0 +1,21 @@
+local sheet = husky.graphics:newImage("sheet.png")
+sheet:setFilter("nearest")
+
+-- sheet.png is a row of four 16x16 frames
+local frames = husky.graphics:newQuadGrid(16, 16, sheet)
+local frame_time = 0.2
+local elapsed = 0
+
+function husky.update(dt)
+	elapsed = elapsed + dt
+end
+
+function husky.draw()
+	husky.graphics:clear(0.1, 0.1, 0.12)
+
+	local frame = math.floor(elapsed / frame_time) % #frames + 1
+	husky.graphics:draw(sheet, frames[frame], 100, 100, 0, 8)
+
+	-- The whole sheet, for reference
+	husky.graphics:draw(sheet, 300, 100, 0, 4)
+end
@@ -0,0 +1,32 @@
+local smiley = husky.graphics:newImage("smiley.png")
+smiley:setFilter("nearest")
+
+local w, h = husky.graphics:getSize()
+local columns, rows = math.floor(w / 32), math.floor(h / 32)
+local batch = husky.graphics:newSpriteBatch(smiley, columns * rows, "dynamic")
+
+-- Fill the screen once, the sprites stay on the GPU after that
+for y = 0, rows - 1 do
+	for x = 0, columns - 1 do
+		batch:setColor(x / columns, y / rows, 1)
+		batch:add(x * 32, y * 32, 0, 2)
+	end
+end
+
+local elapsed = 0
+
+function husky.update(dt)
+	elapsed = elapsed + dt
+
+	-- Only the changed sprite gets uploaded again
+	batch:setColor(1, 1, 1)
+	batch:set(1, 16, 16, elapsed, 2, 2, 8, 8)
+end
+
+function husky.draw()
+	husky.graphics:clear(0.1, 0.1, 0.1)
+	husky.graphics:draw(batch)
+
+	local stats = husky.graphics:getStats()
+	husky.graphics:print(batch:getCount() .. " sprites in " .. stats.drawcalls .. " draw calls", 10, 10)
+end
@@ -0,0 +1,24 @@
+-- Run with --show-fps to see the FPS overlay.
+local ticks = 0
+local message = "waiting..."
+
+husky.timer.after(2, function()
+	message = "2 seconds have passed"
+end)
+
+local ticker = husky.timer.every(0.5, function()
+	ticks = ticks + 1
+end)
+
+husky.timer.after(5, function()
+	husky.timer.cancel(ticker)
+end)
+
+function husky.draw()
+	husky.graphics:clear(0.15, 0.2, 0.25)
+	husky.graphics:print(string.format("time: %.2f", husky.timer.getTime()), 0, 40)
+	husky.graphics:print("fps: " .. husky.timer.getFPS(), 0, 70)
+	husky.graphics:print(string.format("average delta: %.4f", husky.timer.getAverageDelta()), 0, 100)
+	husky.graphics:print("ticks: " .. ticks, 0, 130)
+	husky.graphics:print(message, 0, 160)
+end
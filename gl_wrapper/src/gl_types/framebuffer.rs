@@ -1,12 +1,15 @@
+use std::sync::Arc;
+
 use crate::gl_types::Texture;
 
 /// A framebuffer to render to. Currently does not support 3D textures or render buffers.
 pub struct Framebuffer {
     pub id: gl::types::GLuint,
 
-    //Attachments
-    pub col: Option<Texture>,
+    //Attachments, shared as the textures usually get drawn somewhere else too
+    pub col: Vec<Arc<Texture>>,
     pub depth: Option<Texture>,
+    pub depth_stencil: Option<Texture>,
 }
 
 impl Framebuffer {
@@ -18,18 +21,27 @@ impl Framebuffer {
         Self {
             id: id,
 
-            col: None,
+            col: Vec::new(),
             depth: None,
+            depth_stencil: None,
         }
     }
 
+    /// Attaches `textures` to `COLOR_ATTACHMENT0` onwards and draws into all of them.
+    /// Anything attached before that isn't replaced gets detached.
     //TODO: Support 3D textures too
-    pub fn set_color_attachment(&mut self, texture: Texture) {
+    pub fn set_color_attachments(&mut self, textures: Vec<Arc<Texture>>) {
         self.bind();
+        let attachment_count = textures.len().max(self.col.len());
         unsafe {
-            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture.id, 0);
+            for i in 0..attachment_count {
+                let id = textures.get(i).map_or(0, |texture| texture.id);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0 + i as u32, gl::TEXTURE_2D, id, 0);
+            }
+            let draw_buffers: Vec<gl::types::GLenum> = (0..textures.len()).map(|i| gl::COLOR_ATTACHMENT0 + i as u32).collect();
+            gl::DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr());
         }
-        self.col = Some(texture); //Take ownership of texture, we don't want to drop it
+        self.col = textures; //Keep the textures alive while they're attached
         self.unbind();
     }
 
@@ -42,6 +54,16 @@ impl Framebuffer {
         self.unbind();
     }
 
+    /// Attaches a combined depth and stencil texture, or detaches it when `None`.
+    pub fn set_depth_stencil_attachment(&mut self, texture: Option<Texture>) {
+        self.bind();
+        unsafe {
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_STENCIL_ATTACHMENT, gl::TEXTURE_2D, texture.as_ref().map_or(0, |texture| texture.id), 0);
+        }
+        self.depth_stencil = texture; //Take ownership
+        self.unbind();
+    }
+
     pub fn bind(&self) {
         unsafe {
             gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
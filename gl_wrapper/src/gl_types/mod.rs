@@ -14,6 +14,7 @@ pub use framebuffer::Framebuffer;
 
 use super::shader::ShaderProgram;
 
+/// Values are set with `glProgramUniform*`, so the shader doesn't need to be bound.
 pub trait UniformValue {
     fn update(&self, shader: &ShaderProgram, name: &CString);
 }
@@ -22,27 +23,27 @@ pub trait UniformValue {
 impl UniformValue for f32 {
     fn update(&self, shader: &ShaderProgram, name: &CString) {
         let loc = unsafe { gl::GetUniformLocation(shader.id, name.as_ptr()) };
-        unsafe { gl::Uniform1f(loc, *self); }
+        unsafe { gl::ProgramUniform1f(shader.id, loc, *self); }
     }
 }
 
 impl UniformValue for i32 {
     fn update(&self, shader: &ShaderProgram, name: &CString) {
         let loc = unsafe { gl::GetUniformLocation(shader.id, name.as_ptr()) };
-        unsafe { gl::Uniform1i(loc, *self); }
+        unsafe { gl::ProgramUniform1i(shader.id, loc, *self); }
     }
 }
 
 impl UniformValue for u32 {
     fn update(&self, shader: &ShaderProgram, name: &CString) {
         let loc = unsafe { gl::GetUniformLocation(shader.id, name.as_ptr()) };
-        unsafe { gl::Uniform1ui(loc, *self); }
+        unsafe { gl::ProgramUniform1ui(shader.id, loc, *self); }
     }
 }
 
 impl UniformValue for bool {
     fn update(&self, shader: &ShaderProgram, name: &CString) {
         let loc = unsafe { gl::GetUniformLocation(shader.id, name.as_ptr()) };
-        unsafe { gl::Uniform1i(loc, *self as i32); }
+        unsafe { gl::ProgramUniform1i(shader.id, loc, *self as i32); }
     }
 }
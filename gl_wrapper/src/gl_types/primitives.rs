@@ -40,7 +40,7 @@ impl From<(f32, f32)> for f32_f32 {
 impl UniformValue for f32_f32 {
     fn update(&self, shader: &ShaderProgram, name: &CString) {
         let loc = unsafe { gl::GetUniformLocation(shader.id, name.as_ptr()) };
-        unsafe { gl::Uniform2f(loc, self.d0, self.d1); }
+        unsafe { gl::ProgramUniform2f(shader.id, loc, self.d0, self.d1); }
     }
 }
 
@@ -82,7 +82,7 @@ impl From<(f32, f32, f32)> for f32_f32_f32 {
 impl UniformValue for f32_f32_f32 {
     fn update(&self, shader: &ShaderProgram, name: &CString) {
         let loc = unsafe { gl::GetUniformLocation(shader.id, name.as_ptr()) };
-        unsafe { gl::Uniform3f(loc, self.d0, self.d1, self.d2); }
+        unsafe { gl::ProgramUniform3f(shader.id, loc, self.d0, self.d1, self.d2); }
     }
 }
 
@@ -125,13 +125,13 @@ impl From<(f32, f32, f32, f32)> for f32_f32_f32_f32 {
 impl UniformValue for f32_f32_f32_f32 {
     fn update(&self, shader: &ShaderProgram, name: &CString) {
         let loc = unsafe { gl::GetUniformLocation(shader.id, name.as_ptr()) };
-        unsafe { gl::Uniform4f(loc, self.d0, self.d1, self.d2, self.d3); }
+        unsafe { gl::ProgramUniform4f(shader.id, loc, self.d0, self.d1, self.d2, self.d3); }
     }
 }
 
 impl UniformValue for glam::Mat4 {
     fn update(&self, shader: &ShaderProgram, name: &CString) {
         let loc = unsafe { gl::GetUniformLocation(shader.id, name.as_ptr()) };
-        unsafe { gl::UniformMatrix4fv(loc, 1, gl::FALSE, self.to_cols_array().as_ptr()); }
+        unsafe { gl::ProgramUniformMatrix4fv(shader.id, loc, 1, gl::FALSE, self.to_cols_array().as_ptr()); }
     }
 }
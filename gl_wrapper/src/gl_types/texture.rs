@@ -42,6 +42,67 @@ impl Texture {
         }
     }
 
+    /// Replaces a part of the texture. Rows in `data` are tightly packed, without any padding.
+    pub fn sub_data(&self, offset: (i32, i32), size: (i32, i32), data: &[u8]) {
+        self.bind();
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexSubImage2D(gl::TEXTURE_2D, 0, offset.0, offset.1, size.0, size.1, self.internal_format, self.raw_format, data.as_ptr() as *const c_void);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        }
+        self.unbind();
+    }
+
+    /// Picks which channel each of red, green, blue and alpha is read from when sampling, e.g. `gl::RED` or `gl::ONE`.
+    pub fn set_swizzle(&self, swizzle: [gl::types::GLenum; 4]) {
+        let swizzle = [swizzle[0] as i32, swizzle[1] as i32, swizzle[2] as i32, swizzle[3] as i32];
+        self.bind();
+        unsafe {
+            gl::TexParameteriv(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr());
+        }
+        self.unbind();
+    }
+
+    /// Sets the minification and magnification filters, e.g. `gl::LINEAR`.
+    pub fn set_filter(&self, min: gl::types::GLenum, mag: gl::types::GLenum) {
+        self.bind();
+        unsafe {
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, min as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, mag as i32);
+        }
+        self.unbind();
+    }
+
+    /// The minification and magnification filters.
+    pub fn filter(&self) -> (gl::types::GLenum, gl::types::GLenum) {
+        (self.parameter(gl::TEXTURE_MIN_FILTER), self.parameter(gl::TEXTURE_MAG_FILTER))
+    }
+
+    /// Sets the wrapping on the horizontal and vertical axis, e.g. `gl::REPEAT`.
+    pub fn set_wrap(&self, s: gl::types::GLenum, t: gl::types::GLenum) {
+        self.bind();
+        unsafe {
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, s as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, t as i32);
+        }
+        self.unbind();
+    }
+
+    /// The wrapping on the horizontal and vertical axis.
+    pub fn wrap(&self) -> (gl::types::GLenum, gl::types::GLenum) {
+        (self.parameter(gl::TEXTURE_WRAP_S), self.parameter(gl::TEXTURE_WRAP_T))
+    }
+
+    fn parameter(&self, name: gl::types::GLenum) -> gl::types::GLenum {
+        let mut value = 0;
+        self.bind();
+        unsafe {
+            gl::GetTexParameteriv(gl::TEXTURE_2D, name, &mut value);
+        }
+        self.unbind();
+        value as gl::types::GLenum
+    }
+
     pub fn bind(&self) {
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.id);
@@ -36,7 +36,7 @@ pub struct Vertex {
 #[derive(Clone)]
 pub struct Mesh {
     vert_count: i32,
-    _vbo: ArrayBuffer,
+    vbo: ArrayBuffer,
     vao: VertexArray,
 }
 
@@ -61,11 +61,19 @@ impl Mesh {
 
         Self {
             vert_count: vcount,
-            _vbo: vbo,
+            vbo: vbo,
             vao: vao,
         }
     }
 
+    /// Replaces all vertices, for meshes that change between draws.
+    pub fn set_vertices(&mut self, vertices: &[Vertex]) {
+        self.vert_count = vertices.len() as i32;
+        self.vbo.bind();
+        self.vbo.data(vertices, gl::DYNAMIC_DRAW);
+        self.vbo.unbind();
+    }
+
     /// Make sure to bind a shader first!
     pub fn draw(&self) {
         unsafe {
@@ -1,7 +1,9 @@
 use std::ffi::CString;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use super::util;
-use super::gl_types::UniformValue;
+use super::gl_types::{UniformValue, Texture};
 
 #[derive(Clone)]
 pub struct Shader {
@@ -31,28 +33,34 @@ impl Drop for Shader {
     }
 }
 
-#[derive(Clone)]
 pub struct ShaderProgram {
     pub id: gl::types::GLuint,
+    ///Textures for the samplers, by texture unit. They get bound along with the program.
+    textures: Mutex<HashMap<gl::types::GLuint, Arc<Texture>>>,
 }
 
 impl ShaderProgram {
-    pub fn from_shaders(shaders: Vec<&Shader>) -> Self {
+    /// Links the shaders into a program, returning the link log if that fails.
+    pub fn from_shaders(shaders: Vec<&Shader>) -> Result<Self, String> {
         let mut ids = Vec::new();
         for shader in shaders {
             ids.push(shader.id);
         }
-        let id = program_from_ids(ids);
-        Self {
-            id: id
-        }
+        let id = match program_from_ids(ids) {
+            Ok(id) => id,
+            Err(why) => {
+                error!("Shader program failed to link: {}", why);
+                return Err(why);
+            },
+        };
+        Ok(Self {
+            id: id,
+            textures: Mutex::new(HashMap::new()),
+        })
     }
 
-    pub fn from_shader(shader: &Shader) -> Self {
-        let id = program_from_ids(vec![shader.id]);
-        Self {
-            id: id
-        }
+    pub fn from_shader(shader: &Shader) -> Result<Self, String> {
+        Self::from_shaders(vec![shader])
     }
 
     pub fn uniform(&self, name: &str, val: impl UniformValue) {
@@ -60,9 +68,20 @@ impl ShaderProgram {
         val.update(self, &cname);
     }
 
+    /// Sets the texture bound to `unit` whenever the program is bound.
+    pub fn set_texture(&self, unit: gl::types::GLuint, texture: Arc<Texture>) {
+        self.textures.lock().expect("Failed to acquire lock on shader textures!").insert(unit, texture);
+    }
+
+    /// Binds the program and the textures of its samplers. Texture unit 0 is left active.
     pub fn bind(&self) {
         unsafe {
             gl::UseProgram(self.id);
+            for (unit, texture) in self.textures.lock().expect("Failed to acquire lock on shader textures!").iter() {
+                gl::ActiveTexture(gl::TEXTURE0 + unit);
+                texture.bind();
+            }
+            gl::ActiveTexture(gl::TEXTURE0);
         }
     }
 
@@ -81,7 +100,7 @@ impl Drop for ShaderProgram {
     }
 }
 
-fn program_from_ids(ids: Vec<gl::types::GLuint>) -> gl::types::GLuint {
+fn program_from_ids(ids: Vec<gl::types::GLuint>) -> Result<gl::types::GLuint, String> {
     let id = unsafe { gl::CreateProgram() };
 
     for shader_id in &ids {
@@ -94,7 +113,33 @@ fn program_from_ids(ids: Vec<gl::types::GLuint>) -> gl::types::GLuint {
         unsafe { gl::DetachShader(id, *shader_id); }
     }
 
-    id
+    let mut success: gl::types::GLint = 1;
+    unsafe {
+        gl::GetProgramiv(id, gl::LINK_STATUS, &mut success);
+    }
+
+    if success == 0 {
+        let mut len: gl::types::GLint = 0;
+        unsafe {
+            gl::GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut len);
+        }
+
+        let error = util::create_whitespace_cstring_with_len(len as usize);
+
+        unsafe {
+            gl::GetProgramInfoLog(
+                id,
+                len,
+                std::ptr::null_mut(),
+                error.as_ptr() as *mut gl::types::GLchar
+            );
+            gl::DeleteProgram(id);
+        }
+
+        return Err(util::info_log_to_string(&error));
+    }
+
+    Ok(id)
 }
 
 fn shader_from_source(source: &str, kind: gl::types::GLuint) -> Result<gl::types::GLuint, String> {
@@ -129,7 +174,9 @@ fn shader_from_source(source: &str, kind: gl::types::GLuint) -> Result<gl::types
             );
         }
 
-        return Err(error.to_string_lossy().into_owned());
+        unsafe { gl::DeleteShader(id); }
+
+        return Err(util::info_log_to_string(&error));
     }
 
     Ok(id)
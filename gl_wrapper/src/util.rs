@@ -8,3 +8,8 @@ pub fn create_whitespace_cstring_with_len(len: usize) -> CString {
     // convert buffer to CString
     unsafe { CString::from_vec_unchecked(buffer) }
 }
+
+/// Info logs end in a null terminator inside the buffer, and often in a newline before that.
+pub fn info_log_to_string(log: &CString) -> String {
+    log.to_string_lossy().trim_end_matches(|c: char| c == '\0' || c.is_whitespace()).to_string()
+}
@@ -0,0 +1,108 @@
+#[macro_use] extern crate log;
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use zip::ZipArchive;
+
+mod pack;
+pub use pack::pack_directory;
+
+/// Extension used for packaged games.
+pub const ARCHIVE_EXTENSION: &str = "husky";
+
+/// A packaged game, opened once and shared by everything that loads from it.
+pub struct Archive {
+    path: PathBuf,
+    //Reading from a zip needs mutable access
+    zip: Mutex<ZipArchive<File>>,
+    files: HashSet<String>,
+}
+
+/// The files that make up a game. All paths passed in are relative to the
+/// root of the game and use `/` as the separator, no matter the platform.
+#[derive(Clone)]
+pub enum GameFs {
+    /// A plain folder on disk.
+    Directory(PathBuf),
+    /// A `.husky` archive, which is a zip file with `main.lua` at its root.
+    Archive(Arc<Archive>),
+}
+
+impl GameFs {
+    pub fn from_directory<P: AsRef<Path>>(path: P) -> Self {
+        Self::Directory(path.as_ref().to_path_buf())
+    }
+
+    pub fn from_archive<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let zip = ZipArchive::new(File::open(path)?)?;
+        let files: HashSet<String> = zip.file_names()
+            .filter(|name| !name.ends_with('/'))
+            .map(|name| name.to_string())
+            .collect();
+        debug!("Opened archive `{}` with {} files", path.display(), files.len());
+
+        Ok(Self::Archive(Arc::new(Archive {
+            path: path.to_path_buf(),
+            zip: Mutex::new(zip),
+            files: files,
+        })))
+    }
+
+    /// Returns true if the path points to something we should open as an archive.
+    pub fn is_archive_path(path: &Path) -> bool {
+        path.is_file() && path.extension().map_or(false, |ext| ext == ARCHIVE_EXTENSION || ext == "zip")
+    }
+
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Directory(root) => std::fs::read(root.join(path)),
+            Self::Archive(archive) => {
+                let mut zip = archive.zip.lock().unwrap();
+                let mut file = zip.by_name(normalize(path)).map_err(|_| {
+                    io::Error::new(io::ErrorKind::NotFound, format!("`{}` not found in `{}`", path, archive.path.display()))
+                })?;
+                let mut data = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut data)?;
+                Ok(data)
+            },
+        }
+    }
+
+    pub fn read_to_string(&self, path: &str) -> io::Result<String> {
+        let bytes = self.read(path)?;
+        String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn is_file(&self, path: &str) -> bool {
+        match self {
+            Self::Directory(root) => root.join(path).is_file(),
+            Self::Archive(archive) => archive.files.contains(normalize(path)),
+        }
+    }
+
+    /// The folder on disk, if the game isn't packaged.
+    pub fn directory(&self) -> Option<&Path> {
+        match self {
+            Self::Directory(root) => Some(root),
+            Self::Archive(_) => None,
+        }
+    }
+
+    /// Where the game was loaded from, for use in messages.
+    pub fn display(&self) -> String {
+        match self {
+            Self::Directory(root) => root.display().to_string(),
+            Self::Archive(archive) => archive.path.display().to_string(),
+        }
+    }
+}
+
+/// Zip entries never start with `./` or `/`, but paths from Lua might.
+fn normalize(path: &str) -> &str {
+    path.trim_start_matches("./").trim_start_matches('/')
+}
@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use zip::ZipWriter;
+use zip::write::FileOptions;
+use zip::CompressionMethod;
+
+/// Packs a game folder into a `.husky` archive at `output`.
+/// Hidden files and other `.husky` archives are left out.
+/// Returns the amount of files that were packed.
+pub fn pack_directory(directory: &Path, output: &Path) -> io::Result<usize> {
+    if !directory.join("main.lua").is_file() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("`{}` has no main.lua", directory.display())));
+    }
+
+    let mut zip = ZipWriter::new(File::create(output)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let count = pack_recursive(&mut zip, options, directory, "")?;
+    zip.finish()?;
+
+    Ok(count)
+}
+
+fn pack_recursive(zip: &mut ZipWriter<File>, options: FileOptions, directory: &Path, prefix: &str) -> io::Result<usize> {
+    let mut count = 0;
+
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') { continue; }
+
+        //Zip entries always use `/`, no matter the platform
+        let archive_name = format!("{}{}", prefix, name);
+        let path = entry.path();
+        if path.is_dir() {
+            count += pack_recursive(zip, options, &path, &format!("{}/", archive_name))?;
+        } else if path.extension().map_or(true, |ext| ext != super::ARCHIVE_EXTENSION) {
+            trace!("Packing `{}`", archive_name);
+            zip.start_file(archive_name, options)?;
+            zip.write_all(&std::fs::read(&path)?)?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
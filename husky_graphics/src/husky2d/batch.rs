@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use glam::*;
+
+use gl_wrapper::gl_types::{ArrayBuffer, VertexArray, Texture};
+use gl_wrapper::mesh::Vertex;
+use gl_wrapper::shader::ShaderProgram;
+
+/// Flush once this many vertices are waiting, so a single flush never uploads an enormous buffer.
+const MAX_VERTICES: usize = 3 * 16384;
+
+/// Everything that has to match for two draws to end up in the same draw call.
+#[derive(Clone)]
+pub struct BatchState {
+    pub shader: Arc<ShaderProgram>,
+    pub texture: Arc<Texture>,
+    ///`gl::TRIANGLES`, lines are drawn as triangles too.
+    pub primitive: gl::types::GLenum,
+}
+
+impl PartialEq for BatchState {
+    fn eq(&self, other: &Self) -> bool {
+        self.shader.id == other.shader.id && self.texture.id == other.texture.id && self.primitive == other.primitive
+    }
+}
+
+/// Rendering counters, see `husky.graphics:getStats()`.
+#[derive(Clone, Copy, Default)]
+pub struct Stats {
+    pub draw_calls: u32,
+    pub vertices: u32,
+}
+
+/// Collects vertices from consecutive draws that share the same state,
+/// and sends them to the GPU together when the state changes.
+pub struct Batch {
+    vertices: Vec<Vertex>,
+    state: Option<BatchState>,
+
+    vbo: ArrayBuffer,
+    vao: VertexArray,
+
+    ///Counters for the frame that is being drawn.
+    pub stats: Stats,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        let vbo = ArrayBuffer::new();
+        let vao = VertexArray::new();
+        vao.bind();
+        vbo.bind();
+        vao.attrib_pointers();
+        vbo.unbind();
+        vao.unbind();
+
+        Self {
+            vertices: Vec::with_capacity(MAX_VERTICES),
+            state: None,
+
+            vbo: vbo,
+            vao: vao,
+
+            stats: Stats::default(),
+        }
+    }
+
+    /// Adds vertices drawn with `state`, flushing first if they can't share a draw call with what's already waiting.
+    /// Vertices are expected to already be transformed, in pixels.
+    pub fn push<I: IntoIterator<Item = Vertex>>(&mut self, state: &BatchState, projection: Mat4, vertices: I) {
+        if self.state.as_ref() != Some(state) || self.vertices.len() >= MAX_VERTICES {
+            self.flush(projection);
+            self.state = Some(state.clone());
+        }
+        self.vertices.extend(vertices);
+    }
+
+    /// Draws everything that's waiting.
+    pub fn flush(&mut self, projection: Mat4) {
+        let state = match &self.state {
+            Some(state) if !self.vertices.is_empty() => state,
+            _ => return,
+        };
+
+        state.shader.bind();
+        state.shader.uniform("mvp", projection);
+        unsafe { gl::ActiveTexture(gl::TEXTURE0); }
+        state.texture.bind();
+
+        self.vao.bind();
+        self.vbo.bind();
+        //Orphans the previous buffer, so we don't have to wait on the GPU to finish with it
+        self.vbo.data(&self.vertices, gl::STREAM_DRAW);
+        unsafe {
+            gl::DrawArrays(state.primitive, 0, self.vertices.len() as i32);
+        }
+        self.vbo.unbind();
+        self.vao.unbind();
+
+        state.texture.unbind();
+        state.shader.unbind();
+
+        self.stats.draw_calls += 1;
+        self.stats.vertices += self.vertices.len() as u32;
+        self.vertices.clear();
+    }
+}
+
+/// Moves `vertices` through `transform` and tints them with `color`.
+pub fn transform_vertices<'a>(vertices: &'a [Vertex], transform: Mat4, color: (f32, f32, f32, f32)) -> impl Iterator<Item = Vertex> + 'a {
+    vertices.iter().map(move |v| {
+        let pos = transform.transform_point3(vec3(v.pos.d0, v.pos.d1, v.pos.d2));
+        Vertex {
+            pos: (pos.x, pos.y, pos.z).into(),
+            uv: v.uv,
+            rgba: (v.rgba.d0 * color.0, v.rgba.d1 * color.1, v.rgba.d2 * color.2, v.rgba.d3 * color.3).into(),
+        }
+    })
+}
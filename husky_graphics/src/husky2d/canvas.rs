@@ -0,0 +1,218 @@
+use glam::*;
+
+use gl_wrapper::gl_types::Texture;
+
+use mlua::prelude::*;
+use mlua::{UserData, UserDataMethods};
+
+use super::Image;
+use super::image::add_texture_methods;
+
+lazy_static! {
+    static ref MAX_DRAW_BUFFERS: usize = {
+        let mut value = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_DRAW_BUFFERS, &mut value) };
+        value as usize
+    };
+}
+
+pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
+    //newCanvas([w, h, format]), the size of the window by default
+    methods.add_method("newCanvas", |_, obj, (w, h, format): (Option<u32>, Option<u32>, Option<String>)| {
+        let win_size = *crate::WINDOW_SIZE.lock().unwrap();
+        let (w, h) = (w.unwrap_or(win_size.0), h.unwrap_or(win_size.1));
+        let format = match format {
+            Some(format) => CanvasFormat::from_str(&format)?,
+            None => CanvasFormat::Rgba8,
+        };
+        if w == 0 || h == 0 {
+            return Err(LuaError::RuntimeError("A canvas needs a width and height above 0!".to_string()));
+        }
+        if w > *super::MAX_IMAGE_DIMENSION || h > *super::MAX_IMAGE_DIMENSION {
+            return Err(LuaError::RuntimeError(format!("{}x{} is larger than the maximum texture size of {}!", w, h, *super::MAX_IMAGE_DIMENSION)));
+        }
+        let mut canvas = Canvas::new(w, h, format);
+        canvas.image = canvas.image.with_renderer(obj);
+        Ok(canvas)
+    });
+
+    //setCanvas(canvas, ...) draws into all of the canvases at once, setCanvas() goes back to the screen
+    methods.add_method("setCanvas", |_, obj, args: LuaMultiValue| {
+        let mut canvases = Vec::new();
+        for value in args {
+            match value {
+                LuaValue::Nil => {},
+                LuaValue::UserData(data) if data.is::<Canvas>() => canvases.push(data.borrow::<Canvas>()?.clone()),
+                _ => return Err(LuaError::RuntimeError("setCanvas expects canvases, or nothing to draw to the screen!".to_string())),
+            }
+        }
+        obj.get_lock().renderer2d.set_canvases(canvases)
+    });
+
+    methods.add_method("getCanvas", |_, obj, ()| {
+        Ok(obj.get_lock().renderer2d.canvases.iter().cloned().collect::<mlua::Variadic<Canvas>>())
+    });
+}
+
+#[derive(Clone, Copy)]
+pub enum CanvasFormat {
+    Rgba8,
+    Rgba16f,
+    Rgba32f,
+    Rgb10a2,
+    R8,
+    Rg8,
+    R16f,
+    R32f,
+}
+
+impl CanvasFormat {
+    fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "rgba8" | "normal" => Ok(Self::Rgba8),
+            "rgba16f" | "hdr" => Ok(Self::Rgba16f),
+            "rgba32f" => Ok(Self::Rgba32f),
+            "rgb10a2" => Ok(Self::Rgb10a2),
+            "r8" => Ok(Self::R8),
+            "rg8" => Ok(Self::Rg8),
+            "r16f" => Ok(Self::R16f),
+            "r32f" => Ok(Self::R32f),
+            _ => Err(LuaError::RuntimeError(format!("Unknown canvas format `{}`, expected rgba8, rgba16f, rgba32f, rgb10a2, r8, rg8, r16f or r32f!", s))),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Rgba8 => "rgba8",
+            Self::Rgba16f => "rgba16f",
+            Self::Rgba32f => "rgba32f",
+            Self::Rgb10a2 => "rgb10a2",
+            Self::R8 => "r8",
+            Self::Rg8 => "rg8",
+            Self::R16f => "r16f",
+            Self::R32f => "r32f",
+        }
+    }
+
+    /// The internal format, pixel format and pixel type to create the texture with.
+    fn gl_formats(&self) -> (gl::types::GLenum, gl::types::GLenum, gl::types::GLenum) {
+        match self {
+            Self::Rgba8 => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            Self::Rgba16f => (gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT),
+            Self::Rgba32f => (gl::RGBA32F, gl::RGBA, gl::FLOAT),
+            Self::Rgb10a2 => (gl::RGB10_A2, gl::RGBA, gl::UNSIGNED_INT_2_10_10_10_REV),
+            Self::R8 => (gl::R8, gl::RED, gl::UNSIGNED_BYTE),
+            Self::Rg8 => (gl::RG8, gl::RG, gl::UNSIGNED_BYTE),
+            Self::R16f => (gl::R16F, gl::RED, gl::HALF_FLOAT),
+            Self::R32f => (gl::R32F, gl::RED, gl::FLOAT),
+        }
+    }
+}
+
+/// An image that can be drawn into, see `husky.graphics:setCanvas`.
+#[derive(Clone)]
+pub struct Canvas {
+    pub image: Image,
+    format: CanvasFormat,
+}
+
+impl Canvas {
+    pub fn new(w: u32, h: u32, format: CanvasFormat) -> Self {
+        let (internal_format, pixel_format, pixel_type) = format.gl_formats();
+        let texture = Texture::from_ptr((w as i32, h as i32), std::ptr::null(), internal_format as i32, pixel_format, pixel_type);
+        Self {
+            image: Image::from_texture(texture),
+            format: format,
+        }
+    }
+}
+
+impl AsRef<Image> for Canvas {
+    fn as_ref(&self) -> &Image {
+        &self.image
+    }
+}
+
+impl UserData for Canvas {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        add_texture_methods(methods);
+        methods.add_method("getFormat", |_, obj, ()| Ok(obj.format.as_str()));
+    }
+}
+
+impl super::Renderer2D {
+    /// Draws into `canvases` from now on, or into the window if there are none.
+    pub fn set_canvases(&mut self, canvases: Vec<Canvas>) -> LuaResult<()> {
+        if canvases.len() > *MAX_DRAW_BUFFERS {
+            return Err(LuaError::RuntimeError(format!("Can only draw into {} canvases at once!", *MAX_DRAW_BUFFERS)));
+        }
+        if let Some(first) = canvases.first() {
+            let size = first.image.dimensions();
+            if canvases.iter().any(|canvas| canvas.image.dimensions() != size) {
+                return Err(LuaError::RuntimeError("Canvases drawn into at the same time need to be the same size!".to_string()));
+            }
+        }
+
+        //Whatever was drawn so far belongs to the previous target
+        self.flush();
+
+        self.canvas_framebuffer.set_color_attachments(canvases.iter().map(|canvas| canvas.image.texture.clone()).collect());
+        if let Some(first) = canvases.first() {
+            //Every canvas size gets its own stencil buffer, it isn't kept when switching between sizes
+            let size = first.image.dimensions();
+            let stencil_size = self.canvas_framebuffer.depth_stencil.as_ref().map(|texture| texture.size);
+            if stencil_size != Some((size.0 as i32, size.1 as i32)) {
+                let texture = Texture::from_ptr((size.0 as i32, size.1 as i32), std::ptr::null(), gl::DEPTH24_STENCIL8 as i32, gl::DEPTH_STENCIL, gl::UNSIGNED_INT_24_8);
+                self.canvas_framebuffer.set_depth_stencil_attachment(Some(texture));
+            }
+        }
+        if canvases.is_empty() {
+            self.canvas_framebuffer.unbind();
+        } else {
+            if self.canvas_framebuffer.status() != gl::FRAMEBUFFER_COMPLETE {
+                self.canvas_framebuffer.set_color_attachments(Vec::new());
+                self.canvas_framebuffer.unbind();
+                self.canvases.clear();
+                self.apply_viewport();
+                self.apply_render_state();
+                return Err(LuaError::RuntimeError("Can't draw into these canvases, their formats aren't supported together!".to_string()));
+            }
+            self.canvas_framebuffer.bind();
+        }
+        self.canvases = canvases;
+        self.apply_viewport();
+        //The scissor depends on the size and orientation of what's drawn into
+        self.apply_render_state();
+        Ok(())
+    }
+
+    pub fn is_canvas_active(&self, canvas: &Canvas) -> bool {
+        self.canvases.iter().any(|active| active.image.texture.id == canvas.image.texture.id)
+    }
+
+    /// The size of what's being drawn into, either the canvases or the window.
+    pub fn target_size(&self) -> (u32, u32) {
+        match self.canvases.first() {
+            Some(canvas) => canvas.image.dimensions(),
+            None => *crate::WINDOW_SIZE.lock().unwrap(),
+        }
+    }
+
+    pub fn apply_viewport(&self) {
+        let size = self.target_size();
+        unsafe {
+            gl::Viewport(0,0, size.0 as i32, size.1 as i32);
+        }
+    }
+
+    /// Pixels to clip space, with the origin in the top left and y pointing down.
+    pub fn projection(&self) -> Mat4 {
+        let size = self.target_size();
+        if self.canvases.is_empty() {
+            Mat4::orthographic_rh_gl(0.0, size.0 as f32, size.1 as f32, 0.0, -1.0, 1.0)
+        } else {
+            //Textures start at the bottom row, so canvases are drawn upside down to come out the right way up when they're drawn
+            Mat4::orthographic_rh_gl(0.0, size.0 as f32, 0.0, size.1 as f32, -1.0, 1.0)
+        }
+    }
+}
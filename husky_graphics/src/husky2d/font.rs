@@ -0,0 +1,449 @@
+use std::ops::Range;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{Ordering, AtomicUsize};
+
+use rusttype::{Scale, ScaledGlyph, PositionedGlyph, GlyphId, point};
+
+use mlua::prelude::*;
+use mlua::{UserData, UserDataMethods};
+
+pub const DEFAULT_FONT_SIZE: f32 = 24.0;
+
+static NEXT_FACE_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
+    //newFont(path, [size]), or newFont([size]) for the built in font
+    methods.add_method("newFont", |_, obj, (source, size): (LuaValue, Option<f32>)| {
+        let renderer = obj.get_lock();
+        let (face, size) = match source {
+            LuaValue::Nil => (renderer.default_font.face.clone(), size),
+            LuaValue::Integer(size) => (renderer.default_font.face.clone(), Some(size as f32)),
+            LuaValue::Number(size) => (renderer.default_font.face.clone(), Some(size as f32)),
+            LuaValue::String(path) => {
+                let path = path.to_str()?;
+                let bytes = renderer.fs.read(path).map_err(|e| LuaError::RuntimeError(format!("Failed to read font `{}`: {}", path, e)))?;
+                let face = FontFace::from_bytes(bytes).ok_or_else(|| LuaError::RuntimeError(format!("Failed to load font `{}`: not a TrueType or OpenType font", path)))?;
+                (face, size)
+            },
+            _ => return Err(LuaError::RuntimeError("newFont expects the path to a font, a size, or both!".to_string())),
+        };
+        let size = size.unwrap_or(DEFAULT_FONT_SIZE);
+        if !(size > 0.0) {
+            return Err(LuaError::RuntimeError("A font needs a size above 0!".to_string()));
+        }
+        Ok(Font::new(face, size))
+    });
+
+    methods.add_method("setFont", |_, obj, font: Font| {
+        obj.get_lock().active_font = font;
+        Ok(())
+    });
+    methods.add_method("getFont", |_, obj, ()| Ok(obj.get_lock().active_font.clone()));
+}
+
+/// A loaded TTF or OTF file. The id tells apart the glyphs of different faces in the glyph atlas.
+#[derive(Clone)]
+pub struct FontFace {
+    pub id: usize,
+    font: rusttype::Font<'static>,
+}
+
+impl FontFace {
+    pub fn from_bytes(bytes: Vec<u8>) -> Option<Self> {
+        Some(Self {
+            id: NEXT_FACE_ID.fetch_add(1, Ordering::Relaxed),
+            font: rusttype::Font::try_from_vec(bytes)?,
+        })
+    }
+
+    /// Whether the face has a glyph for `c`, instead of falling back to the missing glyph box.
+    fn has_glyph(&self, c: char) -> bool {
+        self.font.glyph(c).id() != GlyphId(0)
+    }
+}
+
+struct FontSettings {
+    line_height: f32,
+    ///Searched in order for glyphs the font itself doesn't have.
+    fallbacks: Vec<FontFace>,
+}
+
+/// A face at a size, see `husky.graphics:newFont`.
+#[derive(Clone)]
+pub struct Font {
+    face: FontFace,
+    size: f32,
+    //Shared by every copy, so changing a font that's already set changes what gets printed
+    settings: Arc<Mutex<FontSettings>>,
+}
+
+/// A glyph positioned in pixels, with the id of the face it comes from and the color it's printed in.
+pub struct LaidOutGlyph {
+    pub face_id: usize,
+    pub glyph: PositionedGlyph<'static>,
+    pub color: (f32, f32, f32, f32),
+}
+
+/// Text split into characters, each with its own color, see `ColoredText::from_lua`.
+pub struct ColoredText {
+    chars: Vec<(char, (f32, f32, f32, f32))>,
+}
+
+impl ColoredText {
+    pub fn plain(text: &str) -> Self {
+        let mut colored = Self { chars: Vec::with_capacity(text.len()) };
+        colored.push_str(text, (1.0, 1.0, 1.0, 1.0));
+        colored
+    }
+
+    /// Reads either a string, or a table like `{ {1,0,0}, "red", {1,1,1}, "white" }`.
+    /// The colors get multiplied by the current color when printing.
+    pub fn from_lua(value: LuaValue) -> LuaResult<Self> {
+        let table = match value {
+            LuaValue::String(text) => return Ok(Self::plain(text.to_str()?)),
+            LuaValue::Integer(number) => return Ok(Self::plain(&number.to_string())),
+            LuaValue::Number(number) => return Ok(Self::plain(&number.to_string())),
+            LuaValue::Table(table) => table,
+            _ => return Err(LuaError::RuntimeError("Expected a string, or a table of colors and strings!".to_string())),
+        };
+        let mut colored = Self { chars: Vec::new() };
+        let mut color = (1.0, 1.0, 1.0, 1.0);
+        for value in table.sequence_values::<LuaValue>() {
+            match value? {
+                LuaValue::String(text) => colored.push_str(text.to_str()?, color),
+                LuaValue::Table(rgba) => {
+                    let (r, g, b, a): (f32, f32, f32, Option<f32>) = (rgba.get(1)?, rgba.get(2)?, rgba.get(3)?, rgba.get(4)?);
+                    color = (r, g, b, a.unwrap_or(1.0));
+                },
+                _ => return Err(LuaError::RuntimeError("Colored text can only contain colors and strings!".to_string())),
+            }
+        }
+        Ok(colored)
+    }
+
+    fn push_str(&mut self, text: &str, color: (f32, f32, f32, f32)) {
+        //Control characters other than newlines have nothing to draw
+        self.chars.extend(text.chars().filter(|&c| c == '\n' || !c.is_control()).map(|c| (c, color)));
+    }
+
+    fn slice_to_string(&self, range: Range<usize>) -> String {
+        self.chars[range].iter().map(|&(c, _)| c).collect()
+    }
+
+    /// Every line, split at the newlines.
+    fn lines(&self) -> Vec<Range<usize>> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for (i, &(c, _)) in self.chars.iter().enumerate() {
+            if c == '\n' {
+                lines.push(start..i);
+                start = i + 1;
+            }
+        }
+        lines.push(start..self.chars.len());
+        lines
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    ///Stretches the spaces so every line but the last of a paragraph fills the limit.
+    Justify,
+}
+
+impl TextAlign {
+    pub fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "left" => Ok(Self::Left),
+            "center" => Ok(Self::Center),
+            "right" => Ok(Self::Right),
+            "justify" => Ok(Self::Justify),
+            _ => Err(LuaError::RuntimeError(format!("Unknown align mode `{}`, expected left, center, right or justify!", s))),
+        }
+    }
+}
+
+/// A character placed on a row, at `x` from where the row starts.
+struct PlacedChar {
+    face_id: usize,
+    glyph: ScaledGlyph<'static>,
+    x: f32,
+}
+
+impl Font {
+    pub fn new(face: FontFace, size: f32) -> Self {
+        Self {
+            face: face,
+            size: size,
+            settings: Arc::new(Mutex::new(FontSettings {
+                line_height: 1.0,
+                fallbacks: Vec::new(),
+            })),
+        }
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    fn settings(&self) -> MutexGuard<'_, FontSettings> {
+        self.settings.lock().expect("Failed to acquire lock on font settings!")
+    }
+
+    fn v_metrics(&self) -> rusttype::VMetrics {
+        self.face.font.v_metrics(Scale::uniform(self.size))
+    }
+
+    pub fn ascent(&self) -> f32 {
+        self.v_metrics().ascent
+    }
+
+    /// Negative, as it's measured downwards from the baseline.
+    pub fn descent(&self) -> f32 {
+        self.v_metrics().descent
+    }
+
+    /// The height of one line of text, before the line height is applied.
+    pub fn height(&self) -> f32 {
+        let v_metrics = self.v_metrics();
+        v_metrics.ascent - v_metrics.descent + v_metrics.line_gap
+    }
+
+    /// The distance between the tops of two lines.
+    pub fn line_spacing(&self) -> f32 {
+        self.height() * self.settings().line_height
+    }
+
+    /// Places characters next to each other at `scale` times the font size, returning them and where the row ends.
+    fn place(&self, chars: &[(char, (f32, f32, f32, f32))], scale: f32) -> (Vec<PlacedChar>, f32) {
+        let settings = self.settings();
+        let scale = Scale::uniform(self.size * scale);
+        let mut placed: Vec<PlacedChar> = Vec::with_capacity(chars.len());
+        let mut caret = 0.0;
+        for &(c, _) in chars {
+            //Glyphs missing from every face show the missing glyph box of the font itself
+            let face = std::iter::once(&self.face)
+                .chain(settings.fallbacks.iter())
+                .find(|face| face.has_glyph(c))
+                .unwrap_or(&self.face);
+            let glyph = face.font.glyph(c).scaled(scale);
+
+            //Kerning only exists between glyphs of the same face
+            if let Some(last) = placed.last() {
+                if last.face_id == face.id {
+                    caret += face.font.pair_kerning(scale, last.glyph.id(), glyph.id());
+                }
+            }
+
+            let advance = glyph.h_metrics().advance_width;
+            placed.push(PlacedChar { face_id: face.id, glyph: glyph, x: caret });
+            caret += advance;
+        }
+        (placed, caret)
+    }
+
+    /// Splits `text` into lines no wider than `limit`, breaking at spaces where possible.
+    /// The spaces a line is broken at aren't part of either line.
+    pub fn wrap(&self, text: &ColoredText, limit: f32) -> Vec<Range<usize>> {
+        let mut lines = Vec::new();
+        for paragraph in text.lines() {
+            let chars = &text.chars[paragraph.clone()];
+            let (placed, end) = self.place(chars, 1.0);
+            let x = |i: usize| if i < placed.len() { placed[i].x } else { end };
+            let is_space = |i: usize| chars[i].0 == ' ';
+
+            let mut start = 0;
+            loop {
+                //Where the line fits up to, and the last space it could be broken at before that.
+                //The limit gets a little slack, so text measured with getWidth fits in exactly that width
+                let mut fits = start;
+                let mut last_space = None;
+                while fits < chars.len() {
+                    if is_space(fits) {
+                        last_space = Some(fits);
+                    } else if x(fits + 1) - x(start) > limit + 0.01 {
+                        break;
+                    }
+                    fits += 1;
+                }
+
+                let line_end = match last_space {
+                    _ if fits == chars.len() => fits,
+                    Some(space) if space > start => space,
+                    //A word that doesn't fit on a line of its own gets split, at least one character per line
+                    _ => fits.max(start + 1),
+                };
+                let mut trimmed = line_end;
+                while trimmed > start && is_space(trimmed - 1) {
+                    trimmed -= 1;
+                }
+                lines.push(paragraph.start + start..paragraph.start + trimmed);
+
+                start = line_end;
+                while start < chars.len() && is_space(start) {
+                    start += 1;
+                }
+                if start >= chars.len() {
+                    break;
+                }
+            }
+        }
+        lines
+    }
+
+    /// Lays out `lines` of `text` below each other, with the top of the first line at 0.
+    /// Lines are aligned within `limit` when it's given, everything is multiplied by `scale`.
+    fn layout_lines(&self, text: &ColoredText, lines: &[Range<usize>], limit: Option<(f32, TextAlign)>, scale: f32) -> Vec<LaidOutGlyph> {
+        let ascent = self.ascent() * scale;
+        let line_spacing = self.line_spacing() * scale;
+        let mut glyphs = Vec::with_capacity(text.chars.len());
+        for (i, line) in lines.iter().enumerate() {
+            let chars = &text.chars[line.clone()];
+            let (placed, width) = self.place(chars, scale);
+
+            let (x, space_extra) = match limit {
+                None | Some((_, TextAlign::Left)) => (0.0, 0.0),
+                Some((limit, TextAlign::Center)) => ((limit * scale - width) / 2.0, 0.0),
+                Some((limit, TextAlign::Right)) => (limit * scale - width, 0.0),
+                Some((limit, TextAlign::Justify)) => {
+                    //The last line of a paragraph is followed by a newline or the end of the text, not by a space it was wrapped at
+                    let spaces = chars.iter().filter(|&&(c, _)| c == ' ').count();
+                    let wrapped = text.chars.get(line.end).map_or(false, |&(c, _)| c == ' ');
+                    if wrapped && spaces > 0 {
+                        (0.0, (limit * scale - width).max(0.0) / spaces as f32)
+                    } else {
+                        (0.0, 0.0)
+                    }
+                },
+            };
+
+            let y = ascent + i as f32 * line_spacing;
+            let mut spaces = 0;
+            for (&(c, color), placed) in chars.iter().zip(placed) {
+                let offset = x + spaces as f32 * space_extra;
+                if c == ' ' {
+                    spaces += 1;
+                }
+                glyphs.push(LaidOutGlyph {
+                    face_id: placed.face_id,
+                    glyph: placed.glyph.positioned(point(placed.x + offset, y)),
+                    color: color,
+                });
+            }
+        }
+        glyphs
+    }
+
+    /// Lays out every line of `text`, with the top of the first line at 0.
+    pub fn layout(&self, text: &ColoredText, scale: f32) -> Vec<LaidOutGlyph> {
+        self.layout_lines(text, &text.lines(), None, scale)
+    }
+
+    /// Lays out `text` wrapped to `limit` and aligned within it, with the top of the first line at 0.
+    pub fn layout_wrapped(&self, text: &ColoredText, limit: f32, align: TextAlign, scale: f32) -> Vec<LaidOutGlyph> {
+        self.layout_lines(text, &self.wrap(text, limit), Some((limit, align)), scale)
+    }
+
+    /// How wide a range of `text` is, in pixels.
+    fn line_width(&self, text: &ColoredText, line: Range<usize>) -> f32 {
+        self.place(&text.chars[line], 1.0).1
+    }
+
+    /// How wide the widest line of `text` is, in pixels.
+    pub fn width(&self, text: &ColoredText) -> f32 {
+        text.lines().into_iter().map(|line| self.line_width(text, line)).fold(0.0, f32::max)
+    }
+}
+
+impl UserData for Font {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("getWidth", |_, obj, text: String| Ok(obj.width(&ColoredText::plain(&text))));
+        methods.add_method("getHeight", |_, obj, ()| Ok(obj.height()));
+        methods.add_method("getAscent", |_, obj, ()| Ok(obj.ascent()));
+        methods.add_method("getDescent", |_, obj, ()| Ok(obj.descent()));
+        methods.add_method("getSize", |_, obj, ()| Ok(obj.size));
+
+        //The line height is a multiplier, 2 leaves a line of space between every line
+        methods.add_method("setLineHeight", |_, obj, line_height: f32| {
+            obj.settings().line_height = line_height;
+            Ok(())
+        });
+        methods.add_method("getLineHeight", |_, obj, ()| Ok(obj.settings().line_height));
+
+        //getWrap(text, limit) returns the width of the widest line, and a table of the lines printf would print
+        methods.add_method("getWrap", |lua, obj, (text, limit): (LuaValue, f32)| {
+            let text = ColoredText::from_lua(text)?;
+            let lines = obj.wrap(&text, limit);
+            let width = lines.iter().map(|line| obj.line_width(&text, line.clone())).fold(0.0, f32::max);
+            let table = lua.create_sequence_from(lines.into_iter().map(|line| text.slice_to_string(line)))?;
+            Ok((width, table))
+        });
+
+        //setFallbacks(font, ...) replaces the fonts that glyphs missing from this one are taken from
+        methods.add_method("setFallbacks", |_, obj, fonts: mlua::Variadic<Font>| {
+            obj.settings().fallbacks = fonts.iter().map(|font| font.face.clone()).collect();
+            Ok(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font() -> Font {
+        let face = FontFace::from_bytes(include_bytes!("../../../fonts/RobotoMono-Regular.ttf").to_vec()).unwrap();
+        Font::new(face, DEFAULT_FONT_SIZE)
+    }
+
+    fn wrap(font: &Font, text: &str, limit: f32) -> Vec<String> {
+        let text = ColoredText::plain(text);
+        font.wrap(&text, limit).into_iter().map(|line| text.slice_to_string(line)).collect()
+    }
+
+    /// How wide `text` is, which is a safe limit as the font is monospaced.
+    fn width(font: &Font, text: &str) -> f32 {
+        font.width(&ColoredText::plain(text))
+    }
+
+    #[test]
+    fn text_that_fits_stays_on_one_line() {
+        let font = font();
+        assert_eq!(wrap(&font, "hello world", width(&font, "hello world")), vec!["hello world"]);
+    }
+
+    #[test]
+    fn breaks_at_spaces() {
+        let font = font();
+        assert_eq!(wrap(&font, "hello world", width(&font, "hello wor")), vec!["hello", "world"]);
+        //Every space at the break is dropped
+        assert_eq!(wrap(&font, "hello   world", width(&font, "hello")), vec!["hello", "world"]);
+    }
+
+    #[test]
+    fn splits_words_that_are_too_long() {
+        let font = font();
+        assert_eq!(wrap(&font, "hello", width(&font, "hel")), vec!["hel", "lo"]);
+        //At least one character per line, even when that doesn't fit either
+        assert_eq!(wrap(&font, "ab", 1.0), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn keeps_newlines() {
+        let font = font();
+        assert_eq!(wrap(&font, "a\n\nb", 1000.0), vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn colored_text_keeps_colors_per_character() {
+        let lua = Lua::new();
+        let value = lua.load(r#"{ {1, 0, 0}, "ab", {0, 1, 0, 0.5}, "c" }"#).eval().unwrap();
+        let text = ColoredText::from_lua(value).unwrap();
+        assert_eq!(text.slice_to_string(0..3), "abc");
+        assert_eq!(text.chars[1].1, (1.0, 0.0, 0.0, 1.0));
+        assert_eq!(text.chars[2].1, (0.0, 1.0, 0.0, 0.5));
+    }
+}
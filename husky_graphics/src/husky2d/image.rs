@@ -0,0 +1,290 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use glam::*;
+
+use gl_wrapper::gl_types::Texture;
+use gl_wrapper::mesh::Vertex;
+use gl_wrapper::shader::ShaderProgram;
+
+use mlua::prelude::*;
+use mlua::{UserData, UserDataMethods};
+
+use super::Quad;
+use super::batch::{BatchState, transform_vertices};
+
+pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
+    methods.add_method("newImage", |_, obj, path: String| {
+        let fs = obj.get_lock().fs.clone();
+        let bytes = fs.read(&path).map_err(|e| LuaError::RuntimeError(format!("Failed to read image `{}`: {}", path, e)))?;
+        let image = Image::from_bytes(&bytes).map_err(|e| LuaError::RuntimeError(format!("Failed to load image `{}`: {}", path, e)))?;
+        Ok(image.with_renderer(obj))
+    });
+}
+
+#[derive(Clone, Copy)]
+pub enum FilterMode {
+    Linear,
+    Nearest,
+}
+
+impl FilterMode {
+    fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "nearest" => Ok(Self::Nearest),
+            _ => Err(LuaError::RuntimeError(format!("Unknown filter mode `{}`, expected linear or nearest!", s))),
+        }
+    }
+
+    fn from_gl_enum(value: gl::types::GLenum) -> Self {
+        match value {
+            gl::NEAREST => Self::Nearest,
+            _ => Self::Linear,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Nearest => "nearest",
+        }
+    }
+
+    fn gl_enum(&self) -> gl::types::GLenum {
+        match self {
+            Self::Linear => gl::LINEAR,
+            Self::Nearest => gl::NEAREST,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum WrapMode {
+    Clamp,
+    ClampZero,
+    Repeat,
+    MirroredRepeat,
+}
+
+impl WrapMode {
+    fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "clamp" => Ok(Self::Clamp),
+            "clampzero" => Ok(Self::ClampZero),
+            "repeat" => Ok(Self::Repeat),
+            "mirroredrepeat" => Ok(Self::MirroredRepeat),
+            _ => Err(LuaError::RuntimeError(format!("Unknown wrap mode `{}`, expected clamp, clampzero, repeat or mirroredrepeat!", s))),
+        }
+    }
+
+    fn from_gl_enum(value: gl::types::GLenum) -> Self {
+        match value {
+            gl::CLAMP_TO_BORDER => Self::ClampZero,
+            gl::REPEAT => Self::Repeat,
+            gl::MIRRORED_REPEAT => Self::MirroredRepeat,
+            _ => Self::Clamp,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Clamp => "clamp",
+            Self::ClampZero => "clampzero",
+            Self::Repeat => "repeat",
+            Self::MirroredRepeat => "mirroredrepeat",
+        }
+    }
+
+    fn gl_enum(&self) -> gl::types::GLenum {
+        match self {
+            Self::Clamp => gl::CLAMP_TO_EDGE,
+            //Border colour is transparent black by default
+            Self::ClampZero => gl::CLAMP_TO_BORDER,
+            Self::Repeat => gl::REPEAT,
+            Self::MirroredRepeat => gl::MIRRORED_REPEAT,
+        }
+    }
+}
+
+/// A picture loaded into a texture, ready to be drawn.
+#[derive(Clone)]
+pub struct Image {
+    //Shared, as dropping a texture deletes it on the GPU.
+    //Filter and wrap modes live on the texture, so every handle to it agrees on them
+    pub texture: Arc<Texture>,
+    ///Draws batched with the old filter or wrap mode have to be flushed before they change.
+    renderer: Option<Weak<Mutex<crate::Renderer>>>,
+}
+
+impl Image {
+    /// Decodes a PNG or JPEG file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let decoded = image::load_from_memory(bytes).map_err(|e| e.to_string())?.to_rgba8();
+        let (w, h) = decoded.dimensions();
+        if w > *super::MAX_IMAGE_DIMENSION || h > *super::MAX_IMAGE_DIMENSION {
+            return Err(format!("{}x{} is larger than the maximum texture size of {}", w, h, *super::MAX_IMAGE_DIMENSION));
+        }
+
+        let texture = Texture::new((w as i32, h as i32), decoded.as_raw(), gl::RGBA8 as i32, gl::RGBA, gl::UNSIGNED_BYTE);
+        Ok(Self::from_texture(texture))
+    }
+
+    /// Wraps a texture, with linear filtering and clamped edges.
+    pub fn from_texture(texture: Texture) -> Self {
+        texture.set_filter(gl::LINEAR, gl::LINEAR);
+        texture.set_wrap(gl::CLAMP_TO_EDGE, gl::CLAMP_TO_EDGE);
+        Self {
+            texture: Arc::new(texture),
+            renderer: None,
+        }
+    }
+
+    pub fn with_renderer(mut self, renderer: &crate::RendererGuard) -> Self {
+        self.renderer = Some(renderer.downgrade());
+        self
+    }
+
+    fn flush_renderer(&self) {
+        if let Some(renderer) = self.renderer.as_ref().and_then(|renderer| renderer.upgrade()) {
+            renderer.lock().expect("Failed to acquire lock on renderer!").flush();
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.texture.size.0 as u32, self.texture.size.1 as u32)
+    }
+}
+
+impl AsRef<Image> for Image {
+    fn as_ref(&self) -> &Image {
+        self
+    }
+}
+
+/// Methods for everything that wraps a texture, like images and canvases.
+pub fn add_texture_methods<'lua, T: UserData + AsRef<Image>, M: UserDataMethods<'lua, T>>(methods: &mut M) {
+    methods.add_method("getDimensions", |_, obj, ()| Ok(obj.as_ref().dimensions()));
+    methods.add_method("getWidth", |_, obj, ()| Ok(obj.as_ref().dimensions().0));
+    methods.add_method("getHeight", |_, obj, ()| Ok(obj.as_ref().dimensions().1));
+
+    methods.add_method("setFilter", |_, obj, (min, mag): (String, Option<String>)| {
+        let min = FilterMode::from_str(&min)?;
+        let mag = match mag {
+            Some(mag) => FilterMode::from_str(&mag)?,
+            None => min,
+        };
+        let image = obj.as_ref();
+        image.flush_renderer();
+        image.texture.set_filter(min.gl_enum(), mag.gl_enum());
+        Ok(())
+    });
+    methods.add_method("getFilter", |_, obj, ()| {
+        let (min, mag) = obj.as_ref().texture.filter();
+        Ok((FilterMode::from_gl_enum(min).as_str(), FilterMode::from_gl_enum(mag).as_str()))
+    });
+
+    methods.add_method("setWrap", |_, obj, (horizontal, vertical): (String, Option<String>)| {
+        let horizontal = WrapMode::from_str(&horizontal)?;
+        let vertical = match vertical {
+            Some(vertical) => WrapMode::from_str(&vertical)?,
+            None => horizontal,
+        };
+        let image = obj.as_ref();
+        image.flush_renderer();
+        image.texture.set_wrap(horizontal.gl_enum(), vertical.gl_enum());
+        Ok(())
+    });
+    methods.add_method("getWrap", |_, obj, ()| {
+        let (horizontal, vertical) = obj.as_ref().texture.wrap();
+        Ok((WrapMode::from_gl_enum(horizontal).as_str(), WrapMode::from_gl_enum(vertical).as_str()))
+    });
+}
+
+impl UserData for Image {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        add_texture_methods(methods);
+    }
+}
+
+/// Position, rotation, scale and origin of a drawn image, in pixels and radians.
+pub struct DrawTransform {
+    pub x: f32,
+    pub y: f32,
+    pub r: f32,
+    pub sx: f32,
+    pub sy: f32,
+    pub ox: f32,
+    pub oy: f32,
+}
+
+impl DrawTransform {
+    /// Reads the `x, y, r, sx, sy, ox, oy` arguments that every `draw` call takes.
+    /// Also returns the quad, if one was passed in front of them.
+    pub fn from_lua_args(lua: &Lua, args: LuaMultiValue) -> LuaResult<(Option<Quad>, Self)> {
+        let mut args = args.into_vec();
+        let quad = match args.first() {
+            Some(LuaValue::UserData(data)) if data.is::<Quad>() => {
+                let quad = *data.borrow::<Quad>()?;
+                args.remove(0);
+                Some(quad)
+            },
+            _ => None,
+        };
+        let (x,y, r, sx,sy, ox,oy): (Option<f32>,Option<f32>, Option<f32>, Option<f32>,Option<f32>, Option<f32>,Option<f32>) = lua.unpack_multi(LuaMultiValue::from_vec(args))?;
+
+        let sx = sx.unwrap_or(1.0);
+        let transform = Self {
+            x: x.unwrap_or(0.0),
+            y: y.unwrap_or(0.0),
+            r: r.unwrap_or(0.0),
+            sx: sx,
+            sy: sy.unwrap_or(sx),
+            ox: ox.unwrap_or(0.0),
+            oy: oy.unwrap_or(0.0),
+        };
+        Ok((quad, transform))
+    }
+
+    /// The transform, in pixels.
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_translation(vec3(self.x, self.y, 0.0))
+            * Mat4::from_rotation_z(self.r)
+            * Mat4::from_scale(vec3(self.sx, self.sy, 1.0))
+            * Mat4::from_translation(vec3(-self.ox, -self.oy, 0.0))
+    }
+}
+
+/// A unit quad showing the part of the texture between the top left and bottom right UV.
+pub fn quad_vertices(uv_rect: ((f32, f32), (f32, f32))) -> Vec<Vertex> {
+    let ((u0, v0), (u1, v1)) = uv_rect;
+    let corner = |x: f32, y: f32| Vertex {
+        pos: (x, y, 0.0).into(),
+        uv: (u0 + x * (u1 - u0), v0 + y * (v1 - v0)).into(),
+        rgba: (1.0, 1.0, 1.0, 1.0).into(),
+    };
+    vec![
+        corner(0.0, 0.0), corner(1.0, 0.0), corner(1.0, 1.0),
+        corner(0.0, 1.0), corner(0.0, 0.0), corner(1.0, 1.0),
+    ]
+}
+
+impl super::Renderer2D {
+    /// Draws the whole image, or only the part inside `quad`.
+    pub fn image(&mut self, shader: &Arc<ShaderProgram>, color: (f32, f32, f32, f32), image: &Image, quad: Option<&Quad>, t: &DrawTransform) {
+        let (w, h) = match quad {
+            Some(quad) => (quad.w, quad.h),
+            None => (image.dimensions().0 as f32, image.dimensions().1 as f32),
+        };
+        let uv_rect = quad.map_or(((0.0, 0.0), (1.0, 1.0)), |quad| quad.uv_rect());
+
+        let model = t.matrix() * Mat4::from_scale(vec3(w, h, 1.0));
+
+        let state = BatchState {
+            shader: shader.clone(),
+            texture: image.texture.clone(),
+            primitive: gl::TRIANGLES,
+        };
+        let projection = self.projection();
+        self.batch.push(&state, projection, transform_vertices(&quad_vertices(uv_rect), self.transform * model, color));
+    }
+}
@@ -0,0 +1,337 @@
+use std::sync::Arc;
+
+use glam::*;
+
+use gl_wrapper::mesh::Vertex;
+use gl_wrapper::shader::ShaderProgram;
+
+use mlua::prelude::*;
+use mlua::UserDataMethods;
+
+use super::batch::{BatchState, transform_vertices};
+
+/// Miter joins reaching further than this many half line widths from the corner turn into bevels, so sharp corners don't spike out.
+const MITER_LIMIT: f32 = 4.0;
+
+pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
+    methods.add_method("setLineWidth", |_, obj, width: f32| {
+        if !(width > 0.0) {
+            return Err(LuaError::RuntimeError("Line width has to be above 0!".to_string()));
+        }
+        obj.get_lock().renderer2d.line_width = width;
+        Ok(())
+    });
+    methods.add_method("getLineWidth", |_, obj, ()| Ok(obj.get_lock().renderer2d.line_width));
+
+    methods.add_method("setLineJoin", |_, obj, join: String| {
+        obj.get_lock().renderer2d.line_join = LineJoin::from_str(&join)?;
+        Ok(())
+    });
+    methods.add_method("getLineJoin", |_, obj, ()| Ok(obj.get_lock().renderer2d.line_join.as_str()));
+
+    methods.add_method("setLineStyle", |_, obj, style: String| {
+        obj.get_lock().renderer2d.line_style = LineStyle::from_str(&style)?;
+        Ok(())
+    });
+    methods.add_method("getLineStyle", |_, obj, ()| Ok(obj.get_lock().renderer2d.line_style.as_str()));
+}
+
+/// How two segments of an outline are connected.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Bevel,
+    None,
+}
+
+impl LineJoin {
+    fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "miter" => Ok(Self::Miter),
+            "bevel" => Ok(Self::Bevel),
+            "none" => Ok(Self::None),
+            _ => Err(LuaError::RuntimeError(format!("Unknown line join `{}`, expected miter, bevel or none!", s))),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Miter => "miter",
+            Self::Bevel => "bevel",
+            Self::None => "none",
+        }
+    }
+}
+
+/// Smooth lines get a pixel wide fringe that fades out, rough lines have hard edges.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LineStyle {
+    Smooth,
+    Rough,
+}
+
+impl LineStyle {
+    fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "smooth" => Ok(Self::Smooth),
+            "rough" => Ok(Self::Rough),
+            _ => Err(LuaError::RuntimeError(format!("Unknown line style `{}`, expected smooth or rough!", s))),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Smooth => "smooth",
+            Self::Rough => "rough",
+        }
+    }
+}
+
+/// A point on the center of a line, with how far its left and right edge are offset from it for a half width of 1.
+#[derive(Clone, Copy)]
+struct StripPoint {
+    center: Vec2,
+    left: Vec2,
+    right: Vec2,
+}
+
+impl StripPoint {
+    fn straight(center: Vec2, normal: Vec2) -> Self {
+        Self { center: center, left: normal, right: -normal }
+    }
+}
+
+fn normal(from: Vec2, to: Vec2) -> Vec2 {
+    let dir = (to - from).normalize();
+    vec2(-dir.y, dir.x)
+}
+
+/// The offsets at a corner, where `n0` is the normal of the incoming segment and `n1` the one of the outgoing segment.
+/// Returns one point for a miter, or two for a bevel.
+fn join_points(center: Vec2, n0: Vec2, n1: Vec2, join: LineJoin) -> Vec<StripPoint> {
+    let sum = n0 + n1;
+    //The line doubles back on itself, there is no sensible corner to make
+    if sum.length_squared() < 1e-6 {
+        return vec![StripPoint::straight(center, n0), StripPoint::straight(center, n1)];
+    }
+    let miter_dir = sum.normalize();
+    let miter = miter_dir / miter_dir.dot(n0);
+
+    //Positive when the line turns towards its left side, which makes the right side the outside of the corner
+    let turn = n0.perp_dot(n1);
+    if (join == LineJoin::Miter && miter.length() <= MITER_LIMIT) || turn.abs() < 1e-4 {
+        return vec![StripPoint { center: center, left: miter, right: -miter }];
+    }
+    if turn > 0.0 {
+        vec![
+            StripPoint { center: center, left: miter, right: -n0 },
+            StripPoint { center: center, left: miter, right: -n1 },
+        ]
+    } else {
+        vec![
+            StripPoint { center: center, left: n0, right: -miter },
+            StripPoint { center: center, left: n1, right: -miter },
+        ]
+    }
+}
+
+/// Turns a path into strips that run along it. Joins connect the whole path into one strip,
+/// without them every segment gets a strip of its own.
+fn strips(points: &[Vec2], closed: bool, join: LineJoin) -> Vec<Vec<StripPoint>> {
+    let mut path: Vec<Vec2> = Vec::with_capacity(points.len());
+    for &point in points {
+        if path.last().map_or(true, |&last| last.distance_squared(point) > 1e-8) {
+            path.push(point);
+        }
+    }
+    if closed && path.len() > 2 && path[0].distance_squared(path[path.len() - 1]) <= 1e-8 {
+        path.pop();
+    }
+    if path.len() < 2 {
+        return Vec::new();
+    }
+
+    let segment_count = if closed { path.len() } else { path.len() - 1 };
+    let segment = |i: usize| (path[i % path.len()], path[(i + 1) % path.len()]);
+
+    if join == LineJoin::None {
+        return (0..segment_count).map(|i| {
+            let (from, to) = segment(i);
+            let n = normal(from, to);
+            vec![StripPoint::straight(from, n), StripPoint::straight(to, n)]
+        }).collect();
+    }
+
+    let mut strip = Vec::with_capacity(path.len() + 2);
+    if !closed {
+        strip.push(StripPoint::straight(path[0], normal(path[0], path[1])));
+    }
+    let corners = if closed { 0..path.len() } else { 1..path.len() - 1 };
+    for i in corners {
+        let prev = path[(i + path.len() - 1) % path.len()];
+        let next = path[(i + 1) % path.len()];
+        strip.extend(join_points(path[i], normal(prev, path[i]), normal(path[i], next), join));
+    }
+    if closed {
+        let first = strip[0];
+        strip.push(first);
+    } else {
+        let last = path.len() - 1;
+        strip.push(StripPoint::straight(path[last], normal(path[last - 1], path[last])));
+    }
+    vec![strip]
+}
+
+fn line_vertex(pos: Vec2, alpha: f32) -> Vertex {
+    Vertex {
+        pos: (pos.x, pos.y, 0.0).into(),
+        uv: (0.0, 0.0).into(),
+        rgba: (1.0, 1.0, 1.0, alpha).into(),
+    }
+}
+
+/// Triangles for a thick line along `points`, which are in pixels.
+pub fn line_vertices(points: &[Vec2], closed: bool, half_width: f32, join: LineJoin, style: LineStyle) -> Vec<Vertex> {
+    //Each band is a pair of offsets from the center and the alpha at each of them, filled in between
+    let bands: Vec<((f32, f32), (f32, f32))> = match style {
+        LineStyle::Rough => vec![((half_width, 1.0), (-half_width, 1.0))],
+        LineStyle::Smooth => {
+            let core = (half_width - 0.5).max(0.0);
+            //Lines thinner than a pixel fade out instead of getting thinner
+            let alpha = (half_width * 2.0).min(1.0);
+            vec![
+                ((core + 1.0, 0.0), (core, alpha)),
+                ((core, alpha), (-core, alpha)),
+                ((-core, alpha), (-core - 1.0, 0.0)),
+            ]
+        },
+    };
+    let offset = |point: &StripPoint, distance: f32| {
+        if distance >= 0.0 { point.center + point.left * distance } else { point.center - point.right * distance }
+    };
+
+    let mut vertices = Vec::new();
+    for strip in strips(points, closed, join) {
+        for pair in strip.windows(2) {
+            for &((d0, a0), (d1, a1)) in &bands {
+                let outer0 = line_vertex(offset(&pair[0], d0), a0);
+                let inner0 = line_vertex(offset(&pair[0], d1), a1);
+                let outer1 = line_vertex(offset(&pair[1], d0), a0);
+                let inner1 = line_vertex(offset(&pair[1], d1), a1);
+                vertices.extend_from_slice(&[outer0, inner0, outer1, inner0, inner1, outer1]);
+            }
+        }
+    }
+    vertices
+}
+
+impl super::Renderer2D {
+    /// Draws a line along `points` with the current line width, join and style.
+    /// The points go through the current transform, the line width is scaled along with it.
+    pub fn line(&mut self, shader: &Arc<ShaderProgram>, color: (f32, f32, f32, f32), points: &[Vec2], closed: bool) {
+        let transform = self.transform;
+        let points: Vec<Vec2> = points.iter().map(|p| transform.transform_point3(p.extend(0.0)).truncate()).collect();
+        //Lines are built in pixels, so smooth edges stay a pixel wide no matter the transform
+        let half_width = self.line_width * self.transform_scale() / 2.0;
+
+        let vertices = line_vertices(&points, closed, half_width, self.line_join, self.line_style);
+        let state = BatchState {
+            shader: shader.clone(),
+            texture: self.white_texture.clone(),
+            primitive: gl::TRIANGLES,
+        };
+        let projection = self.projection();
+        self.batch.push(&state, projection, transform_vertices(&vertices, Mat4::IDENTITY, color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn straight_lines_are_offset_along_the_normal() {
+        let vertices = line_vertices(&[vec2(0.0, 0.0), vec2(10.0, 0.0)], false, 2.0, LineJoin::Miter, LineStyle::Rough);
+        assert_eq!(vertices.len(), 6);
+        for v in &vertices {
+            //Copied out, as the vertex fields are packed
+            let (x, y, alpha) = (v.pos.d0, v.pos.d1, v.rgba.d3);
+            assert!(x == 0.0 || x == 10.0);
+            assert!(y == 2.0 || y == -2.0);
+            assert_eq!(alpha, 1.0);
+        }
+    }
+
+    #[test]
+    fn smooth_lines_get_faded_edges() {
+        let vertices = line_vertices(&[vec2(0.0, 0.0), vec2(10.0, 0.0)], false, 2.0, LineJoin::Miter, LineStyle::Smooth);
+        assert_eq!(vertices.len(), 18);
+        assert!(vertices.iter().any(|v| {
+            let (y, alpha) = (v.pos.d1, v.rgba.d3);
+            alpha == 0.0 && y.abs() == 2.5
+        }));
+    }
+
+    #[test]
+    fn right_angles_get_one_miter_point() {
+        let points = join_points(vec2(10.0, 0.0), vec2(0.0, 1.0), vec2(-1.0, 0.0), LineJoin::Miter);
+        assert_eq!(points.len(), 1);
+        assert_near(points[0].left, vec2(-1.0, 1.0));
+        assert_near(points[0].right, vec2(1.0, -1.0));
+    }
+
+    #[test]
+    fn bevels_cut_the_outside_of_the_corner() {
+        let points = join_points(vec2(10.0, 0.0), vec2(0.0, 1.0), vec2(-1.0, 0.0), LineJoin::Bevel);
+        assert_eq!(points.len(), 2);
+        //Turning left keeps the miter on the inside, on the left
+        assert_near(points[0].left, vec2(-1.0, 1.0));
+        assert_near(points[1].left, vec2(-1.0, 1.0));
+        assert_near(points[0].right, vec2(0.0, -1.0));
+        assert_near(points[1].right, vec2(1.0, 0.0));
+    }
+
+    #[test]
+    fn sharp_miters_turn_into_bevels() {
+        //Almost doubling back, the miter would reach about 20 half widths out
+        let points = join_points(vec2(10.0, 0.0), vec2(0.0, 1.0), vec2(0.1, -1.0).normalize(), LineJoin::Miter);
+        assert_eq!(points.len(), 2);
+    }
+
+    #[test]
+    fn doubling_back_makes_no_corner() {
+        let points = join_points(vec2(10.0, 0.0), vec2(0.0, 1.0), vec2(0.0, -1.0), LineJoin::Miter);
+        assert_eq!(points.len(), 2);
+        assert_near(points[0].left, vec2(0.0, 1.0));
+        assert_near(points[1].left, vec2(0.0, -1.0));
+    }
+
+    #[test]
+    fn closed_paths_are_one_strip_that_loops() {
+        let square = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0), vec2(0.0, 10.0)];
+        let result = strips(&square, true, LineJoin::Miter);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].len(), 5);
+        assert_near(result[0][0].center, result[0][4].center);
+    }
+
+    #[test]
+    fn no_join_splits_every_segment() {
+        let square = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0), vec2(0.0, 10.0)];
+        let result = strips(&square, true, LineJoin::None);
+        assert_eq!(result.len(), 4);
+        assert!(result.iter().all(|strip| strip.len() == 2));
+    }
+
+    #[test]
+    fn repeated_points_are_skipped() {
+        let result = strips(&[vec2(0.0, 0.0), vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 0.0)], false, LineJoin::Miter);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].len(), 2);
+        assert!(strips(&[vec2(1.0, 1.0), vec2(1.0, 1.0)], false, LineJoin::Miter).is_empty());
+    }
+}
@@ -1,33 +1,103 @@
-use std::sync::{Arc, Mutex};
-
-use image::{DynamicImage, RgbaImage};
+use std::sync::Arc;
 
+use mlua::prelude::*;
 use mlua::UserDataMethods;
 
+use self::image::DrawTransform;
+
 mod text;
+mod font;
 mod primitive;
+mod image;
+mod quad;
+mod batch;
+mod sprite_batch;
+mod transform;
+mod line;
+mod canvas;
+mod render_state;
+mod stencil;
 
 pub use primitive::Drawmode2D;
-
-use gl_wrapper::gl_types::f32_f32;
-use gl_wrapper::gl_types::Texture;
-use gl_wrapper::mesh::{Vertex, Mesh};
-use gl_wrapper::shader::{Shader, ShaderProgram};
+pub use self::image::Image;
+pub use quad::Quad;
+pub use batch::Stats;
+pub use sprite_batch::SpriteBatch;
+pub use line::{LineJoin, LineStyle};
+pub use canvas::Canvas;
+pub use render_state::{RenderState, BlendMode, BlendAlphaMode};
+pub use stencil::{StencilAction, StencilCompare};
+pub use font::{Font, FontFace, DEFAULT_FONT_SIZE};
+
+use gl_wrapper::gl_types::{Texture, Framebuffer};
 
 pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
     primitive::add_methods(methods);
+    self::image::add_methods(methods);
+    quad::add_methods(methods);
+    sprite_batch::add_methods(methods);
+    transform::add_methods(methods);
+    line::add_methods(methods);
+    canvas::add_methods(methods);
+    render_state::add_methods(methods);
+    stencil::add_methods(methods);
+    text::add_methods(methods);
+    font::add_methods(methods);
+
+    //draw(drawable, [quad], x, y, r, sx, sy, ox, oy)
+    methods.add_method("draw", |lua, obj, (drawable, args): (LuaAnyUserData, LuaMultiValue)| {
+        let (quad, transform) = DrawTransform::from_lua_args(lua, args)?;
+        let mut renderer = obj.get_lock();
+        let color = renderer.active_color;
+        let shader = renderer.get_active_shader().raw_program.clone();
+
+        if let Ok(image) = drawable.borrow::<Image>() {
+            renderer.renderer2d.image(&shader, color, &image, quad.as_ref(), &transform);
+        } else if let Ok(canvas) = drawable.borrow::<Canvas>() {
+            if renderer.renderer2d.is_canvas_active(&canvas) {
+                return Err(LuaError::RuntimeError("Can't draw a canvas while drawing into it!".to_string()));
+            }
+            renderer.renderer2d.image(&shader, color, &canvas.image, quad.as_ref(), &transform);
+        } else if let Ok(mut sprite_batch) = drawable.borrow_mut::<SpriteBatch>() {
+            renderer.renderer2d.sprite_batch(&shader, color, &mut sprite_batch, &transform);
+        } else {
+            return Err(LuaError::RuntimeError("Can only draw images, canvases and sprite batches!".to_string()));
+        }
+        Ok(())
+    });
+
+    //Counters from the last finished frame
+    methods.add_method("getStats", |lua, obj, ()| {
+        let stats = obj.get_lock().renderer2d.last_stats;
+        let table = lua.create_table()?;
+        table.set("drawcalls", stats.draw_calls)?;
+        table.set("vertices", stats.vertices)?;
+        Ok(table)
+    });
 }
 
-#[derive(Clone)]
 pub struct Renderer2D {
-    active_fontobj: String,
-
-    //TODO: Abstract to text.rs
-    font_program: ShaderProgram,
-    font_mesh: Mesh,
-    font_image: Arc<Mutex<RgbaImage>>,
-    font_texture: Texture,
-    print_count: u32,
+    glyph_atlas: text::GlyphAtlas,
+
+    ///Bound for anything drawn without a texture, so the default shader can always sample one.
+    white_texture: Arc<Texture>,
+    batch: batch::Batch,
+    pub last_stats: Stats,
+
+    ///Applied to everything drawn in 2D, set through `translate`, `rotate` and friends.
+    pub transform: glam::Mat4,
+    transform_stack: Vec<glam::Mat4>,
+
+    pub line_width: f32,
+    pub line_join: LineJoin,
+    pub line_style: LineStyle,
+    pub point_size: f32,
+
+    ///What's being drawn into, the window when empty.
+    pub canvases: Vec<Canvas>,
+    canvas_framebuffer: Framebuffer,
+
+    pub render_state: RenderState,
 }
 
 lazy_static! {
@@ -39,87 +109,48 @@ lazy_static! {
 }
 
 impl Renderer2D {
-    pub fn new(active_fontobj: String) -> Self {
-        let font_vs = Shader::from_source(include_str!("../../../shaders/vs_text.glsl"), gl::VERTEX_SHADER).expect("Failed to compile font vertex shader!");
-        let font_fs = Shader::from_source(include_str!("../../../shaders/fs_text.glsl"), gl::FRAGMENT_SHADER).expect("Failed to compile font fragment shader!");
-        let font_program = ShaderProgram::from_shaders(vec![&font_vs, &font_fs]);
-
-        let font_mesh_verts: Vec<Vertex> = vec![
-            Vertex {
-                pos: (0.0, 0.0, 0.0).into(),
-                uv: (0.0, 0.0).into(),
-                rgba: (1.0, 1.0, 1.0, 1.0).into(),
-            },
-            Vertex {
-                pos: (1.0, 0.0, 0.0).into(),
-                uv: (1.0, 0.0).into(),
-                rgba: (1.0, 1.0, 1.0, 1.0).into(),
-            },
-            Vertex {
-                pos: (1.0, 1.0, 0.0).into(),
-                uv: (1.0, 1.0).into(),
-                rgba: (1.0, 1.0, 1.0, 1.0).into(),
-            },
-
-            Vertex {
-                pos: (0.0, 1.0, 0.0).into(),
-                uv: (0.0, 1.0).into(),
-                rgba: (1.0, 1.0, 1.0, 1.0).into(),
-            },
-            Vertex {
-                pos: (0.0, 0.0, 0.0).into(),
-                uv: (0.0, 0.0).into(),
-                rgba: (1.0, 1.0, 1.0, 1.0).into(),
-            },
-            Vertex {
-                pos: (1.0, 1.0, 0.0).into(),
-                uv: (1.0, 1.0).into(),
-                rgba: (1.0, 1.0, 1.0, 1.0).into(),
-            },
-        ];
-        let font_mesh = Mesh::from_vertices(&font_mesh_verts);
-
-        let image = DynamicImage::new_rgba8(1280, 720).to_rgba8();
-        let texture = Texture::from_ptr((1280, 720), std::ptr::null(), gl::RGBA as i32, gl::RGBA, gl::UNSIGNED_BYTE);
-
-        Self {
-            active_fontobj: active_fontobj,
-
-            font_program: font_program,
-            font_mesh: font_mesh,
-            font_image: Arc::new(Mutex::new(image)),
-            font_texture: texture,
-            print_count: 0,
-        }
+    pub fn new() -> Self {
+        let renderer = Self {
+            glyph_atlas: text::GlyphAtlas::new(),
+
+            white_texture: Arc::new(Texture::new((1, 1), &[255, 255, 255, 255], gl::RGBA8 as i32, gl::RGBA, gl::UNSIGNED_BYTE)),
+            batch: batch::Batch::new(),
+            last_stats: Stats::default(),
+
+            transform: glam::Mat4::IDENTITY,
+            transform_stack: Vec::new(),
+
+            line_width: 1.0,
+            line_join: LineJoin::Miter,
+            line_style: LineStyle::Smooth,
+            point_size: 1.0,
+
+            canvases: Vec::new(),
+            canvas_framebuffer: Framebuffer::new(),
+
+            render_state: RenderState::default(),
+        };
+        renderer.apply_render_state();
+        renderer
     }
 
-    pub fn finish_frame(&mut self) {
-        if self.print_count > 0 {
-            let win_size: (u32, u32) = {
-                let raw_win_size = crate::WINDOW_SIZE.lock().unwrap();
-                (raw_win_size.0, raw_win_size.1)
-            };
-
-            let ortho_matrix = glam::Mat4::orthographic_rh_gl(0.0, win_size.0 as f32, win_size.1 as f32, 0.0, -1.0, 1.0);
-
-            {
-                let image_lock = self.font_image.lock().unwrap();
-                self.font_texture.bind();
-                self.font_texture.data(image_lock.as_raw());
-                self.font_texture.unbind();
-            }
+    /// Draws everything batched so far. Needed before anything that draws outside of the batch,
+    /// or changes state the batched draws depend on.
+    pub fn flush(&mut self) {
+        let projection = self.projection();
+        self.batch.flush(projection);
+    }
 
-            //Render textured quad with the above image
-            self.font_program.bind();
-            self.font_texture.bind();
-            self.font_program.uniform("offset", f32_f32::from((0f32, 0f32)));
-            self.font_program.uniform("ortho", ortho_matrix);
-            self.font_program.uniform("scale", f32_f32::from( (win_size.0 as f32, win_size.1 as f32) ));
-            self.font_mesh.draw();
-            self.font_texture.unbind();
-            self.font_program.unbind();
-
-            self.print_count = 0;
+    pub fn finish_frame(&mut self) {
+        if !self.canvases.is_empty() {
+            warn!("A canvas was still set at the end of the frame, switching back to the screen");
+            //Going back to the screen has nothing to validate, so this can't fail
+            let _ = self.set_canvases(Vec::new());
         }
+        self.flush();
+
+        self.last_stats = self.batch.stats;
+        self.batch.stats = Stats::default();
+        self.reset_transform();
     }
 }
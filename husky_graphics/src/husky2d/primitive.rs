@@ -1,206 +1,439 @@
-use glam::*;
+use std::f32::consts::PI;
+use std::sync::Arc;
 
-use crate::WINDOW_SIZE;
+use glam::*;
 
-use gl_wrapper::gl_types::f32_f32_f32_f32;
-use gl_wrapper::mesh::{Vertex, Mesh};
+use gl_wrapper::mesh::Vertex;
 use gl_wrapper::shader::ShaderProgram;
 
+use super::batch::{BatchState, transform_vertices};
+
+use mlua::prelude::*;
 use mlua::UserDataMethods;
 
+/// Curves are split into segments until they're at most this far off the real curve, in pixels.
+const CURVE_TOLERANCE: f32 = 0.25;
+const MAX_SEGMENTS: usize = 256;
+/// The most segments a script can ask for, so a typo can't allocate millions of vertices.
+const MAX_REQUESTED_SEGMENTS: usize = 4096;
+
 pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
-    methods.add_method("rect", |_, obj, (mode_raw, x,y, w,h): (String, f32,f32, f32,f32)| {
-        let win_size = *WINDOW_SIZE.lock().unwrap();
-        let mode = Drawmode2D::from_str(&mode_raw);
-        let draw_x = (x / win_size.0 as f32) * 2.0 - 1.0;
-        let draw_y = (y / win_size.1 as f32) * 2.0 - 1.0;
-        let draw_w = (w / win_size.0 as f32) * 2.0;
-        let draw_h = (h / win_size.1 as f32) * 2.0;
+    //rect(mode, x, y, w, h, [rx, ry, segments]), with rx and ry rounding the corners
+    methods.add_method("rect", |_, obj, (mode_raw, x,y, w,h, rx,ry, segments): (String, f32,f32, f32,f32, Option<f32>,Option<f32>, Option<usize>)| {
+        let mode = Drawmode2D::from_str(&mode_raw)?;
+        let segments = check_segments(segments)?;
+        let mut renderer = obj.get_lock();
+        let color = renderer.active_color;
+        let shader = renderer.get_active_shader().raw_program.clone();
+        let rx = rx.unwrap_or(0.0);
+        let path = rect_path(x,y, w,h, rx, ry.unwrap_or(rx), segments, renderer.renderer2d.transform_scale());
+        renderer.renderer2d.convex(&shader, color, mode, &path);
+        Ok(())
+    });
+
+    methods.add_method("circle", |_, obj, (mode_raw, x,y, r, segments): (String, f32,f32, f32, Option<usize>)| {
+        let mode = Drawmode2D::from_str(&mode_raw)?;
+        let segments = check_segments(segments)?;
+        let mut renderer = obj.get_lock();
+        let color = renderer.active_color;
+        let shader = renderer.get_active_shader().raw_program.clone();
+        let path = ellipse_path(x,y, r,r, segments, renderer.renderer2d.transform_scale());
+        renderer.renderer2d.convex(&shader, color, mode, &path);
+        Ok(())
+    });
+
+    methods.add_method("ellipse", |_, obj, (mode_raw, x,y, rx,ry, segments): (String, f32,f32, f32,f32, Option<usize>)| {
+        let mode = Drawmode2D::from_str(&mode_raw)?;
+        let segments = check_segments(segments)?;
         let mut renderer = obj.get_lock();
         let color = renderer.active_color;
-        let shader = renderer.get_active_shader().clone();
-        renderer.renderer2d.rect(&shader.raw_program, color, mode, draw_x,draw_y, draw_w,draw_h);
+        let shader = renderer.get_active_shader().raw_program.clone();
+        let path = ellipse_path(x,y, rx,ry, segments, renderer.renderer2d.transform_scale());
+        renderer.renderer2d.convex(&shader, color, mode, &path);
         Ok(())
     });
 
-    methods.add_method("circle", |_, obj, (mode_raw, x,y, r): (String, f32,f32, f32)| {
-        let win_size = *WINDOW_SIZE.lock().unwrap();
-        let mode = Drawmode2D::from_str(&mode_raw);
-        let draw_x = (x / win_size.0 as f32) * 2.0 - 1.0;
-        let draw_y = (y / win_size.1 as f32) * 2.0 - 1.0;
-        let draw_w = (r / win_size.0 as f32) * 2.0;
-        let draw_h = (r / win_size.1 as f32) * 2.0;
+    //arc(mode, [type], x, y, r, a1, a2, [segments]), type is pie, open or closed
+    methods.add_method("arc", |lua, obj, (mode_raw, args): (String, LuaMultiValue)| {
+        let mode = Drawmode2D::from_str(&mode_raw)?;
+        let mut args = args.into_vec();
+        let arc_type = match args.first() {
+            Some(LuaValue::String(s)) => {
+                let arc_type = ArcType::from_str(s.to_str()?)?;
+                args.remove(0);
+                arc_type
+            },
+            _ => ArcType::Pie,
+        };
+        let (x,y, r, a1,a2, segments): (f32,f32, f32, f32,f32, Option<usize>) = lua.unpack_multi(LuaMultiValue::from_vec(args))?;
+        let segments = check_segments(segments)?;
+
         let mut renderer = obj.get_lock();
         let color = renderer.active_color;
-        let shader = renderer.get_active_shader().clone();
-        renderer.renderer2d.circle(&shader.raw_program, color, mode, draw_x,draw_y, draw_w, draw_h);
+        let shader = renderer.get_active_shader().raw_program.clone();
+        let segments = segments.unwrap_or_else(|| {
+            let full_circle = curve_segments(r * renderer.renderer2d.transform_scale());
+            ((full_circle as f32 * (a2 - a1).abs() / (PI * 2.0)).ceil() as usize).max(1).min(MAX_REQUESTED_SEGMENTS)
+        });
+        let mut path = arc_path(x,y, r, a1,a2, segments);
+        match (arc_type, mode) {
+            (ArcType::Pie, _) => path.insert(0, vec2(x,y)),
+            //Filled open arcs look the same as closed ones, there's no edge to leave out
+            (ArcType::Open, Drawmode2D::Lines) => {
+                renderer.renderer2d.line(&shader, color, &path, false);
+                return Ok(());
+            },
+            _ => {},
+        }
+        renderer.renderer2d.convex(&shader, color, mode, &path);
         Ok(())
     });
 
     methods.add_method("tri", |_, obj, (mode_raw, x,y, w,h): (String, f32,f32, f32,f32)| {
-        let win_size = *WINDOW_SIZE.lock().unwrap();
-        let mode = Drawmode2D::from_str(&mode_raw);
-        let draw_x = (x / win_size.0 as f32) * 2.0 - 1.0;
-        let draw_y = (y / win_size.1 as f32) * 2.0 - 1.0;
-        let draw_w = (w / win_size.0 as f32) * 2.0;
-        let draw_h = (h / win_size.1 as f32) * 2.0;
+        let mode = Drawmode2D::from_str(&mode_raw)?;
+        let mut renderer = obj.get_lock();
+        let color = renderer.active_color;
+        let shader = renderer.get_active_shader().raw_program.clone();
+        let path = [vec2(x - w / 2.0, y + h / 2.0), vec2(x + w / 2.0, y + h / 2.0), vec2(x, y - h / 2.0)];
+        renderer.renderer2d.convex(&shader, color, mode, &path);
+        Ok(())
+    });
+
+    //polygon(mode, x1, y1, x2, y2, ...) or polygon(mode, {x1, y1, x2, y2, ...}), may be concave
+    methods.add_method("polygon", |_, obj, (mode_raw, args): (String, LuaMultiValue)| {
+        let mode = Drawmode2D::from_str(&mode_raw)?;
+        let points = points_from_args(args)?;
+        if points.len() < 3 {
+            return Err(LuaError::RuntimeError("A polygon needs at least 3 points!".to_string()));
+        }
+        let mut renderer = obj.get_lock();
+        let color = renderer.active_color;
+        let shader = renderer.get_active_shader().raw_program.clone();
+        renderer.renderer2d.polygon(&shader, color, mode, &points);
+        Ok(())
+    });
+
+    //line(x1, y1, x2, y2, ...) or line({x1, y1, x2, y2, ...})
+    methods.add_method("line", |_, obj, args: LuaMultiValue| {
+        let points = points_from_args(args)?;
+        if points.len() < 2 {
+            return Err(LuaError::RuntimeError("A line needs at least 2 points!".to_string()));
+        }
         let mut renderer = obj.get_lock();
         let color = renderer.active_color;
-        let shader = renderer.get_active_shader().clone();
-        renderer.renderer2d.tri(&shader.raw_program, color, mode, draw_x,draw_y, draw_w,draw_h);
+        let shader = renderer.get_active_shader().raw_program.clone();
+        renderer.renderer2d.line(&shader, color, &points, false);
         Ok(())
     });
+
+    //points(x1, y1, x2, y2, ...) or points({x1, y1, x2, y2, ...})
+    methods.add_method("points", |_, obj, args: LuaMultiValue| {
+        let points = points_from_args(args)?;
+        let mut renderer = obj.get_lock();
+        let color = renderer.active_color;
+        let shader = renderer.get_active_shader().raw_program.clone();
+        renderer.renderer2d.points(&shader, color, &points);
+        Ok(())
+    });
+
+    methods.add_method("setPointSize", |_, obj, size: f32| {
+        if !(size > 0.0) {
+            return Err(LuaError::RuntimeError("Point size has to be above 0!".to_string()));
+        }
+        obj.get_lock().renderer2d.point_size = size;
+        Ok(())
+    });
+    methods.add_method("getPointSize", |_, obj, ()| Ok(obj.get_lock().renderer2d.point_size));
 }
 
-lazy_static! {
-    static ref RECTANGLE_VERTICES: Vec<Vertex> = vec![
-        Vertex {
-            pos: (0.0, 0.0, 0.0).into(),
-            uv: (0.0, 0.0).into(),
-            rgba: (1.0, 1.0, 1.0, 1.0).into(),
-        },
-        Vertex {
-            pos: (1.0, 0.0, 0.0).into(),
-            uv: (1.0, 0.0).into(),
-            rgba: (1.0, 1.0, 1.0, 1.0).into(),
-        },
-        Vertex {
-            pos: (1.0, 1.0, 0.0).into(),
-            uv: (1.0, 1.0).into(),
-            rgba: (1.0, 1.0, 1.0, 1.0).into(),
-        },
+/// Reads coordinates passed either as numbers or as a single table of numbers.
+fn points_from_args(args: LuaMultiValue) -> LuaResult<Vec<Vec2>> {
+    let args = args.into_vec();
+    let coords: Vec<f32> = match args.as_slice() {
+        [LuaValue::Table(table)] => table.clone().sequence_values::<f32>().collect::<LuaResult<_>>()?,
+        _ => args.into_iter().map(|value| match value {
+            LuaValue::Integer(i) => Ok(i as f32),
+            LuaValue::Number(n) => Ok(n as f32),
+            _ => Err(LuaError::RuntimeError("Expected coordinates as numbers, or as one table of numbers!".to_string())),
+        }).collect::<LuaResult<_>>()?,
+    };
+    if coords.len() % 2 != 0 {
+        return Err(LuaError::RuntimeError("Coordinates come in pairs, got an odd number of them!".to_string()));
+    }
+    Ok(coords.chunks(2).map(|c| vec2(c[0], c[1])).collect())
+}
 
-        Vertex {
-            pos: (0.0, 1.0, 0.0).into(),
-            uv: (0.0, 1.0).into(),
-            rgba: (1.0, 1.0, 1.0, 1.0).into(),
-        },
-        Vertex {
-            pos: (0.0, 0.0, 0.0).into(),
-            uv: (0.0, 0.0).into(),
-            rgba: (1.0, 1.0, 1.0, 1.0).into(),
-        },
-        Vertex {
-            pos: (1.0, 1.0, 0.0).into(),
-            uv: (1.0, 1.0).into(),
-            rgba: (1.0, 1.0, 1.0, 1.0).into(),
-        },
-    ];
+fn check_segments(segments: Option<usize>) -> LuaResult<Option<usize>> {
+    match segments {
+        Some(segments) if segments > MAX_REQUESTED_SEGMENTS => Err(LuaError::RuntimeError(format!("Can't draw more than {} segments, got {}!", MAX_REQUESTED_SEGMENTS, segments))),
+        _ => Ok(segments),
+    }
+}
 
-    static ref RECTANGLE: Mesh = Mesh::from_vertices(&RECTANGLE_VERTICES);
+/// How many segments a full circle with this radius in pixels needs to look round.
+fn curve_segments(radius: f32) -> usize {
+    let radius = radius.abs();
+    if radius <= CURVE_TOLERANCE {
+        return 8;
+    }
+    let step = (1.0 - CURVE_TOLERANCE / radius).acos();
+    ((PI / step).ceil() as usize).max(8).min(MAX_SEGMENTS)
+}
 
-    static ref TRIANGLE_VERTICES: Vec<Vertex> = vec![
-        Vertex {
-            pos: (-0.5, -0.5, 0.0).into(),
-            uv: (0.0, 0.0).into(),
-            rgba: (1.0, 1.0, 1.0, 1.0).into(),
-        },
-        Vertex {
-            pos: (0.5, -0.5, 0.0).into(),
-            uv: (1.0, 0.0).into(),
-            rgba: (1.0, 1.0, 1.0, 1.0).into(),
-        },
-        Vertex {
-            pos: (0.0, 0.5, 0.0).into(),
-            uv: (1.0, 1.0).into(),
-            rgba: (1.0, 1.0, 1.0, 1.0).into(),
-        },
-    ];
+/// Points along an arc from `a1` to `a2`, both ends included.
+fn arc_path(x: f32, y: f32, r: f32, a1: f32, a2: f32, segments: usize) -> Vec<Vec2> {
+    let segments = segments.max(1);
+    (0..=segments).map(|i| {
+        let a = a1 + (a2 - a1) * (i as f32 / segments as f32);
+        vec2(x + a.cos() * r, y + a.sin() * r)
+    }).collect()
+}
+
+fn ellipse_path(x: f32, y: f32, rx: f32, ry: f32, segments: Option<usize>, scale: f32) -> Vec<Vec2> {
+    let segments = segments.unwrap_or_else(|| curve_segments(rx.abs().max(ry.abs()) * scale)).max(3);
+    (0..segments).map(|i| {
+        let a = (i as f32 / segments as f32) * PI * 2.0;
+        vec2(x + a.cos() * rx, y + a.sin() * ry)
+    }).collect()
+}
+
+/// A rectangle, with corners rounded by `rx` and `ry` if they're above 0.
+fn rect_path(x: f32, y: f32, w: f32, h: f32, rx: f32, ry: f32, segments: Option<usize>, scale: f32) -> Vec<Vec2> {
+    let rx = rx.max(0.0).min(w.abs() / 2.0);
+    let ry = ry.max(0.0).min(h.abs() / 2.0);
+    if rx == 0.0 || ry == 0.0 {
+        return vec![vec2(x, y), vec2(x + w, y), vec2(x + w, y + h), vec2(x, y + h)];
+    }
 
-    static ref TRIANGLE: Mesh = Mesh::from_vertices(&TRIANGLE_VERTICES);
-
-    static ref CIRCLE_VERTICES: Vec<Vertex> = {
-        let mut verts = Vec::new();
-
-        for i in 0..32 {
-            let center = Vertex {
-                pos: (0.0, 0.0, 0.0).into(),
-                uv: (0.0, 0.0).into(),
-                rgba: (1.0, 1.0, 1.0, 1.0).into(),
-            };
-            let r = ((i as f32) / 32f32) * std::f32::consts::PI * 2f32;
-            let r_next = (((i + 1) as f32) / 32f32) * std::f32::consts::PI * 2f32;
-            let last = Vertex {
-                pos: (r.cos(), r.sin(), 0.0).into(),
-                uv: (0.0, 0.0).into(),
-                rgba: (1.0, 1.0, 1.0, 1.0).into(),
-            };
-            let next = Vertex {
-                pos: (r_next.cos(), r_next.sin(), 0.0).into(),
-                uv: (0.0, 0.0).into(),
-                rgba: (1.0, 1.0, 1.0, 1.0).into(),
-            };
-            verts.push(center);
-            verts.push(last);
-            verts.push(next);
+    let segments = segments.unwrap_or_else(|| curve_segments(rx.max(ry) * scale) / 4).max(1);
+    let corners = [
+        (vec2(x + w - rx, y + ry), -PI / 2.0),
+        (vec2(x + w - rx, y + h - ry), 0.0),
+        (vec2(x + rx, y + h - ry), PI / 2.0),
+        (vec2(x + rx, y + ry), PI),
+    ];
+    let mut path = Vec::with_capacity((segments + 1) * 4);
+    for &(center, start) in &corners {
+        for i in 0..=segments {
+            let a = start + (PI / 2.0) * (i as f32 / segments as f32);
+            path.push(vec2(center.x + a.cos() * rx, center.y + a.sin() * ry));
         }
+    }
+    path
+}
 
-        verts
-    };
+/// Twice the signed area, positive when the points go clockwise on screen.
+fn signed_area(points: &[Vec2]) -> f32 {
+    (0..points.len()).map(|i| points[i].perp_dot(points[(i + 1) % points.len()])).sum()
+}
+
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    let d0 = (b - a).perp_dot(p - a);
+    let d1 = (c - b).perp_dot(p - b);
+    let d2 = (a - c).perp_dot(p - c);
+    !((d0 < 0.0 || d1 < 0.0 || d2 < 0.0) && (d0 > 0.0 || d1 > 0.0 || d2 > 0.0))
+}
+
+/// Splits a simple polygon into triangles by clipping ears, so it works for concave polygons too.
+pub fn triangulate(points: &[Vec2]) -> Vec<Vec2> {
+    let mut remaining: Vec<Vec2> = points.to_vec();
+    if signed_area(&remaining) < 0.0 {
+        remaining.reverse();
+    }
 
-    static ref CIRCLE: Mesh = Mesh::from_vertices(&CIRCLE_VERTICES);
+    let mut triangles = Vec::with_capacity(points.len().saturating_sub(2) * 3);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+            //Reflex corners can't be ears
+            if (b - a).perp_dot(c - b) <= 0.0 {
+                return false;
+            }
+            !remaining.iter().enumerate().any(|(j, &p)| {
+                j != i && j != (i + n - 1) % n && j != (i + 1) % n && in_triangle(p, a, b, c)
+            })
+        });
+        //Self intersecting polygons can run out of ears, fill what's left as best we can
+        let i = ear.unwrap_or(0);
+        triangles.extend_from_slice(&[remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]]);
+        remaining.remove(i);
+    }
+    triangles.extend_from_slice(&remaining);
+    triangles
+}
+
+fn fan(points: &[Vec2]) -> Vec<Vec2> {
+    let mut triangles = Vec::with_capacity(points.len().saturating_sub(2) * 3);
+    for i in 1..points.len().saturating_sub(1) {
+        triangles.extend_from_slice(&[points[0], points[i], points[i + 1]]);
+    }
+    triangles
 }
 
 #[non_exhaustive]
+#[derive(Clone, Copy)]
 pub enum Drawmode2D {
     Lines,
     Filled,
-    Unknown
 }
 
 impl Drawmode2D {
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_str(s: &str) -> LuaResult<Self> {
         match s {
-            "fill" => Self::Filled,
-            "line" => Self::Lines,
-            _ => Self::Unknown,
+            "fill" => Ok(Self::Filled),
+            "line" => Ok(Self::Lines),
+            _ => Err(LuaError::RuntimeError(format!("Unknown draw mode `{}`, expected fill or line!", s))),
         }
     }
 }
 
-impl super::Renderer2D {
-    pub fn rect(&mut self, shader: &ShaderProgram, color: (f32, f32, f32, f32), mode: Drawmode2D, x: f32, y: f32, w: f32, h: f32) {
-        let scale = vec3(w,h,1f32);
-        let translation = vec3(x,y,1f32);
-        let model = Mat4::from_scale_rotation_translation(scale, Quat::IDENTITY, translation);
-
-        // let shader = self.get_active_shader();
-        shader.uniform("mvp", model);
-        shader.uniform("drawColor", f32_f32_f32_f32::from(color));
-        match mode {
-            Drawmode2D::Filled => RECTANGLE.draw(),
-            Drawmode2D::Lines => RECTANGLE.draw_wireframe(),
-            _ => panic!("Unsupported drawmode!"),
+#[derive(Clone, Copy)]
+enum ArcType {
+    ///Closed through the center.
+    Pie,
+    ///Outlines leave the ends unconnected.
+    Open,
+    ///Closed by a straight line between the ends.
+    Closed,
+}
+
+impl ArcType {
+    fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "pie" => Ok(Self::Pie),
+            "open" => Ok(Self::Open),
+            "closed" => Ok(Self::Closed),
+            _ => Err(LuaError::RuntimeError(format!("Unknown arc type `{}`, expected pie, open or closed!", s))),
         }
     }
+}
 
-    pub fn circle(&mut self, shader: &ShaderProgram, color: (f32, f32, f32, f32), mode: Drawmode2D, x: f32, y: f32, w: f32, h: f32) {
-        let scale = vec3(w,h,1f32);
-        let translation = vec3(x,y,1f32);
-        let model = Mat4::from_scale_rotation_translation(scale, Quat::IDENTITY, translation);
+impl super::Renderer2D {
+    /// Fills triangles given as a list of corners, in pixels before the transform.
+    fn fill(&mut self, shader: &Arc<ShaderProgram>, color: (f32, f32, f32, f32), triangles: &[Vec2]) {
+        let vertices: Vec<Vertex> = triangles.iter().map(|p| Vertex {
+            pos: (p.x, p.y, 0.0).into(),
+            uv: (0.0, 0.0).into(),
+            rgba: (1.0, 1.0, 1.0, 1.0).into(),
+        }).collect();
+        let state = BatchState {
+            shader: shader.clone(),
+            texture: self.white_texture.clone(),
+            primitive: gl::TRIANGLES,
+        };
+        let projection = self.projection();
+        self.batch.push(&state, projection, transform_vertices(&vertices, self.transform, color));
+    }
 
-        // let shader = self.get_active_shader();
-        shader.uniform("mvp", model);
-        shader.uniform("drawColor", f32_f32_f32_f32::from(color));
+    /// Draws a shape that's known to be convex, which can be filled without triangulating it.
+    pub fn convex(&mut self, shader: &Arc<ShaderProgram>, color: (f32, f32, f32, f32), mode: Drawmode2D, path: &[Vec2]) {
         match mode {
-            Drawmode2D::Filled => CIRCLE.draw(),
-            Drawmode2D::Lines => CIRCLE.draw_wireframe(),
-            _ => panic!("Unsupported drawmode!"),
+            Drawmode2D::Filled => self.fill(shader, color, &fan(path)),
+            Drawmode2D::Lines => self.line(shader, color, path, true),
         }
     }
 
-    pub fn tri(&mut self, shader: &ShaderProgram, color: (f32, f32, f32, f32), mode: Drawmode2D, x: f32, y: f32, w: f32, h: f32) {
-        let scale = vec3(w,h,1f32);
-        let translation = vec3(x,y,1f32);
-        let model = Mat4::from_scale_rotation_translation(scale, Quat::IDENTITY, translation);
-
-        // let shader = self.get_active_shader();
-        shader.uniform("mvp", model);
-        shader.uniform("drawColor", f32_f32_f32_f32::from(color));
+    pub fn polygon(&mut self, shader: &Arc<ShaderProgram>, color: (f32, f32, f32, f32), mode: Drawmode2D, path: &[Vec2]) {
         match mode {
-            Drawmode2D::Filled => TRIANGLE.draw(),
-            Drawmode2D::Lines => TRIANGLE.draw_wireframe(),
-            _ => panic!("Unsupported drawmode!"),
+            Drawmode2D::Filled => self.fill(shader, color, &triangulate(path)),
+            Drawmode2D::Lines => self.line(shader, color, path, true),
+        }
+    }
+
+    /// Draws a square of `point_size` pixels at every point. The size ignores the transform, only the positions follow it.
+    pub fn points(&mut self, shader: &Arc<ShaderProgram>, color: (f32, f32, f32, f32), points: &[Vec2]) {
+        let half = self.point_size / 2.0;
+        let transform = self.transform;
+        let mut vertices = Vec::with_capacity(points.len() * 6);
+        for p in points {
+            let p = transform.transform_point3(p.extend(0.0)).truncate();
+            let (a, b, c, d) = (p + vec2(-half, -half), p + vec2(half, -half), p + vec2(half, half), p + vec2(-half, half));
+            for corner in [a, b, c, a, c, d].iter() {
+                vertices.push(Vertex {
+                    pos: (corner.x, corner.y, 0.0).into(),
+                    uv: (0.0, 0.0).into(),
+                    rgba: (1.0, 1.0, 1.0, 1.0).into(),
+                });
+            }
         }
+        let state = BatchState {
+            shader: shader.clone(),
+            texture: self.white_texture.clone(),
+            primitive: gl::TRIANGLES,
+        };
+        let projection = self.projection();
+        self.batch.push(&state, projection, transform_vertices(&vertices, Mat4::IDENTITY, color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Twice the area of every triangle, positive when it has the same winding as a clockwise polygon.
+    fn triangle_areas(triangles: &[Vec2]) -> Vec<f32> {
+        triangles.chunks(3).map(|t| (t[1] - t[0]).perp_dot(t[2] - t[0])).collect()
+    }
+
+    fn assert_covers(points: &[Vec2]) {
+        let triangles = triangulate(points);
+        assert_eq!(triangles.len(), (points.len() - 2) * 3);
+        let areas = triangle_areas(&triangles);
+        assert!(areas.iter().all(|&area| area > 0.0), "a triangle is flipped: {:?}", areas);
+        //Triangles that all face the same way and add up to the polygon's area can't overlap
+        let total: f32 = areas.iter().sum();
+        assert!((total - signed_area(points).abs()).abs() < 1e-3, "{} != {}", total, signed_area(points));
+    }
+
+    #[test]
+    fn triangulates_a_square() {
+        assert_covers(&[vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0), vec2(0.0, 10.0)]);
+    }
+
+    #[test]
+    fn triangulates_either_winding() {
+        assert_covers(&[vec2(0.0, 10.0), vec2(10.0, 10.0), vec2(10.0, 0.0), vec2(0.0, 0.0)]);
+    }
+
+    #[test]
+    fn triangulates_concave_polygons() {
+        //An L shape
+        assert_covers(&[
+            vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 4.0),
+            vec2(4.0, 4.0), vec2(4.0, 10.0), vec2(0.0, 10.0),
+        ]);
+        //An arrow, with the reflex corner first so the first ear isn't at index 0
+        assert_covers(&[
+            vec2(5.0, 6.0), vec2(0.0, 10.0), vec2(5.0, 0.0), vec2(10.0, 10.0),
+        ]);
+    }
+
+    #[test]
+    fn a_triangle_stays_as_it_is() {
+        let points = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(0.0, 10.0)];
+        assert_eq!(triangulate(&points), points.to_vec());
+    }
+
+    #[test]
+    fn fans_convex_shapes() {
+        let points = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0), vec2(0.0, 10.0)];
+        assert_eq!(fan(&points), vec![points[0], points[1], points[2], points[0], points[2], points[3]]);
+        assert!(fan(&points[..2]).is_empty());
+    }
+
+    #[test]
+    fn arcs_include_both_ends() {
+        let path = arc_path(0.0, 0.0, 1.0, 0.0, PI, 4);
+        assert_eq!(path.len(), 5);
+        assert!(path[0].distance(vec2(1.0, 0.0)) < 1e-5);
+        assert!(path[4].distance(vec2(-1.0, 0.0)) < 1e-5);
+    }
+
+    #[test]
+    fn too_many_segments_is_an_error() {
+        assert!(check_segments(Some(MAX_REQUESTED_SEGMENTS)).is_ok());
+        assert!(check_segments(Some(MAX_REQUESTED_SEGMENTS + 1)).is_err());
+        assert!(check_segments(None).is_ok());
     }
 }
@@ -0,0 +1,112 @@
+use mlua::prelude::*;
+use mlua::{UserData, UserDataMethods};
+
+use super::{Image, Canvas};
+
+pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
+    methods.add_method("newQuad", |_, _obj, (x,y, w,h, reference, sh): (f32,f32, f32,f32, LuaValue, Option<f32>)| {
+        let (sw, sh) = reference_size(reference, sh)?;
+        Quad::new(x,y, w,h, sw,sh)
+    });
+
+    //Slices a sprite sheet into frames, left to right and then top to bottom
+    methods.add_method("newQuadGrid", |lua, _obj, (frame_w, frame_h, reference, sh, spacing, margin): (f32, f32, LuaValue, Option<f32>, Option<f32>, Option<f32>)| {
+        let (sw, sh) = reference_size(reference, sh)?;
+        let spacing = spacing.unwrap_or(0.0);
+        let margin = margin.unwrap_or(0.0);
+        if frame_w <= 0.0 || frame_h <= 0.0 {
+            return Err(LuaError::RuntimeError("Grid frames need a width and height above 0!".to_string()));
+        }
+
+        let quads = lua.create_table()?;
+        let mut y = margin;
+        while y + frame_h <= sh - margin {
+            let mut x = margin;
+            while x + frame_w <= sw - margin {
+                quads.raw_set(quads.raw_len() + 1, Quad::new(x,y, frame_w,frame_h, sw,sh)?)?;
+                x += frame_w + spacing;
+            }
+            y += frame_h + spacing;
+        }
+        Ok(quads)
+    });
+}
+
+/// Quads are relative to the size of a texture, which can be passed as a width and height or as an image.
+fn reference_size(reference: LuaValue, sh: Option<f32>) -> LuaResult<(f32, f32)> {
+    match (reference, sh) {
+        (LuaValue::UserData(data), None) => {
+            let (w, h) = match data.borrow::<Canvas>() {
+                Ok(canvas) => canvas.image.dimensions(),
+                Err(_) => data.borrow::<Image>()?.dimensions(),
+            };
+            Ok((w as f32, h as f32))
+        },
+        (LuaValue::Integer(sw), Some(sh)) => Ok((sw as f32, sh)),
+        (LuaValue::Number(sw), Some(sh)) => Ok((sw as f32, sh)),
+        _ => Err(LuaError::RuntimeError("Expected an image or canvas, or the width and height of the texture!".to_string())),
+    }
+}
+
+/// A rectangle inside a texture, in pixels.
+#[derive(Clone, Copy)]
+pub struct Quad {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    /// Size of the texture this quad was made for.
+    pub sw: f32,
+    pub sh: f32,
+}
+
+impl Quad {
+    pub fn new(x: f32, y: f32, w: f32, h: f32, sw: f32, sh: f32) -> LuaResult<Self> {
+        if sw <= 0.0 || sh <= 0.0 {
+            return Err(LuaError::RuntimeError("Quads need a texture size above 0!".to_string()));
+        }
+        Ok(Self { x, y, w, h, sw, sh })
+    }
+
+    /// Returns the texture coordinates of the top left and bottom right corner.
+    pub fn uv_rect(&self) -> ((f32, f32), (f32, f32)) {
+        (
+            (self.x / self.sw, self.y / self.sh),
+            ((self.x + self.w) / self.sw, (self.y + self.h) / self.sh),
+        )
+    }
+}
+
+impl UserData for Quad {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("getViewport", |_, obj, ()| Ok((obj.x, obj.y, obj.w, obj.h)));
+        methods.add_method_mut("setViewport", |_, obj, (x,y, w,h, sw,sh): (f32,f32, f32,f32, Option<f32>,Option<f32>)| {
+            *obj = Quad::new(x,y, w,h, sw.unwrap_or(obj.sw), sh.unwrap_or(obj.sh))?;
+            Ok(())
+        });
+        methods.add_method("getTextureDimensions", |_, obj, ()| Ok((obj.sw, obj.sh)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uv_rect_is_relative_to_the_texture() {
+        let quad = Quad::new(32.0, 16.0, 16.0, 8.0, 64.0, 32.0).unwrap();
+        assert_eq!(quad.uv_rect(), ((0.5, 0.5), (0.75, 0.75)));
+    }
+
+    #[test]
+    fn whole_texture() {
+        let quad = Quad::new(0.0, 0.0, 20.0, 10.0, 20.0, 10.0).unwrap();
+        assert_eq!(quad.uv_rect(), ((0.0, 0.0), (1.0, 1.0)));
+    }
+
+    #[test]
+    fn needs_a_texture_size() {
+        assert!(Quad::new(0.0, 0.0, 1.0, 1.0, 0.0, 10.0).is_err());
+        assert!(Quad::new(0.0, 0.0, 1.0, 1.0, 10.0, -1.0).is_err());
+    }
+}
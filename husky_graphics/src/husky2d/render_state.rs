@@ -0,0 +1,235 @@
+use mlua::prelude::*;
+use mlua::UserDataMethods;
+
+use super::stencil::{StencilAction, StencilCompare};
+
+pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
+    //setBlendMode(mode, [alphamode]), alphamode is alphamultiply by default
+    methods.add_method("setBlendMode", |_, obj, (mode, alpha_mode): (String, Option<String>)| {
+        let mode = BlendMode::from_str(&mode)?;
+        let alpha_mode = match alpha_mode {
+            Some(alpha_mode) => BlendAlphaMode::from_str(&alpha_mode)?,
+            None => BlendAlphaMode::AlphaMultiply,
+        };
+        if mode == BlendMode::Multiply && alpha_mode != BlendAlphaMode::Premultiplied {
+            return Err(LuaError::RuntimeError("The multiply blend mode only works with premultiplied alpha!".to_string()));
+        }
+        obj.get_lock().renderer2d.set_render_state(|state| state.blend = (mode, alpha_mode));
+        Ok(())
+    });
+    methods.add_method("getBlendMode", |_, obj, ()| {
+        let (mode, alpha_mode) = obj.get_lock().renderer2d.render_state.blend;
+        Ok((mode.as_str(), alpha_mode.as_str()))
+    });
+
+    //setColorMask(r, g, b, a), or setColorMask() to write to every channel again
+    methods.add_method("setColorMask", |_, obj, (r, g, b, a): (Option<bool>, Option<bool>, Option<bool>, Option<bool>)| {
+        let mask = match (r, g, b, a) {
+            (Some(r), Some(g), Some(b), Some(a)) => (r, g, b, a),
+            (None, None, None, None) => (true, true, true, true),
+            _ => return Err(LuaError::RuntimeError("setColorMask expects a value for all four channels, or none at all!".to_string())),
+        };
+        obj.get_lock().renderer2d.set_render_state(|state| state.color_mask = mask);
+        Ok(())
+    });
+    methods.add_method("getColorMask", |_, obj, ()| Ok(obj.get_lock().renderer2d.render_state.color_mask));
+
+    //setScissor(x, y, w, h) in pixels, ignoring the transform, or setScissor() to turn it off
+    methods.add_method("setScissor", |_, obj, (x, y, w, h): (Option<i32>, Option<i32>, Option<i32>, Option<i32>)| {
+        let scissor = match (x, y, w, h) {
+            (Some(x), Some(y), Some(w), Some(h)) => Some(Scissor::new(x, y, w, h)),
+            (None, None, None, None) => None,
+            _ => return Err(LuaError::RuntimeError("setScissor expects x, y, width and height, or nothing at all!".to_string())),
+        };
+        obj.get_lock().renderer2d.set_render_state(|state| state.scissor = scissor);
+        Ok(())
+    });
+    //Shrinks the scissor to the part that overlaps with this rectangle, or sets it if there is none
+    methods.add_method("intersectScissor", |_, obj, (x, y, w, h): (i32, i32, i32, i32)| {
+        let rect = Scissor::new(x, y, w, h);
+        obj.get_lock().renderer2d.set_render_state(|state| {
+            state.scissor = Some(state.scissor.map_or(rect, |scissor| scissor.intersect(&rect)));
+        });
+        Ok(())
+    });
+    methods.add_method("getScissor", |_, obj, ()| {
+        match obj.get_lock().renderer2d.render_state.scissor {
+            Some(s) => Ok((Some(s.x), Some(s.y), Some(s.w), Some(s.h))),
+            None => Ok((None, None, None, None)),
+        }
+    });
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Alpha,
+    Add,
+    Subtract,
+    Multiply,
+    Replace,
+    Screen,
+}
+
+impl BlendMode {
+    fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "alpha" => Ok(Self::Alpha),
+            "add" => Ok(Self::Add),
+            "subtract" => Ok(Self::Subtract),
+            "multiply" => Ok(Self::Multiply),
+            "replace" => Ok(Self::Replace),
+            "screen" => Ok(Self::Screen),
+            _ => Err(LuaError::RuntimeError(format!("Unknown blend mode `{}`, expected alpha, add, subtract, multiply, replace or screen!", s))),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Alpha => "alpha",
+            Self::Add => "add",
+            Self::Subtract => "subtract",
+            Self::Multiply => "multiply",
+            Self::Replace => "replace",
+            Self::Screen => "screen",
+        }
+    }
+}
+
+/// Whether colors still have to be multiplied by their alpha when blending, or already are.
+#[derive(Clone, Copy, PartialEq)]
+pub enum BlendAlphaMode {
+    AlphaMultiply,
+    Premultiplied,
+}
+
+impl BlendAlphaMode {
+    fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "alphamultiply" => Ok(Self::AlphaMultiply),
+            "premultiplied" => Ok(Self::Premultiplied),
+            _ => Err(LuaError::RuntimeError(format!("Unknown blend alpha mode `{}`, expected alphamultiply or premultiplied!", s))),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::AlphaMultiply => "alphamultiply",
+            Self::Premultiplied => "premultiplied",
+        }
+    }
+}
+
+/// A rectangle in pixels from the top left of what's being drawn into.
+#[derive(Clone, Copy)]
+pub struct Scissor {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl Scissor {
+    fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        Self { x: x, y: y, w: w.max(0), h: h.max(0) }
+    }
+
+    fn intersect(&self, other: &Scissor) -> Scissor {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.w).min(other.x + other.w);
+        let bottom = (self.y + self.h).min(other.y + other.h);
+        Scissor::new(x, y, right - x, bottom - y)
+    }
+}
+
+/// GL state that stays the same until the game changes it, even across frames.
+#[derive(Clone, Copy)]
+pub struct RenderState {
+    pub blend: (BlendMode, BlendAlphaMode),
+    pub color_mask: (bool, bool, bool, bool),
+    pub scissor: Option<Scissor>,
+    pub stencil_test: Option<(StencilCompare, i32)>,
+    ///Set while drawing inside `stencil`, which only writes to the stencil buffer.
+    pub stencil_write: Option<(StencilAction, i32)>,
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        Self {
+            blend: (BlendMode::Alpha, BlendAlphaMode::AlphaMultiply),
+            color_mask: (true, true, true, true),
+            scissor: None,
+            stencil_test: None,
+            stencil_write: None,
+        }
+    }
+}
+
+impl RenderState {
+    /// Sets the GL state. `target_height` and `flipped` describe what's being drawn into,
+    /// as the scissor is given from the top but GL wants it from the bottom.
+    pub fn apply(&self, target_height: u32, flipped: bool) {
+        let (mode, alpha_mode) = self.blend;
+        //Premultiplied colors are used as they are, others get multiplied by their alpha first
+        let src_rgb = match alpha_mode {
+            BlendAlphaMode::AlphaMultiply => gl::SRC_ALPHA,
+            BlendAlphaMode::Premultiplied => gl::ONE,
+        };
+        let (equation, src_rgb, src_alpha, dst_rgb, dst_alpha) = match mode {
+            BlendMode::Alpha => (gl::FUNC_ADD, src_rgb, gl::ONE, gl::ONE_MINUS_SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA),
+            BlendMode::Add => (gl::FUNC_ADD, src_rgb, gl::ZERO, gl::ONE, gl::ONE),
+            BlendMode::Subtract => (gl::FUNC_REVERSE_SUBTRACT, src_rgb, gl::ZERO, gl::ONE, gl::ONE),
+            BlendMode::Multiply => (gl::FUNC_ADD, gl::DST_COLOR, gl::DST_COLOR, gl::ZERO, gl::ZERO),
+            BlendMode::Replace => (gl::FUNC_ADD, src_rgb, gl::ONE, gl::ZERO, gl::ZERO),
+            BlendMode::Screen => (gl::FUNC_ADD, src_rgb, gl::ONE, gl::ONE_MINUS_SRC_COLOR, gl::ONE_MINUS_SRC_COLOR),
+        };
+
+        let (r, g, b, a) = self.color_mask;
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendEquation(equation);
+            gl::BlendFuncSeparate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+
+            match (self.stencil_write, self.stencil_test) {
+                (Some((action, value)), _) => {
+                    gl::Enable(gl::STENCIL_TEST);
+                    gl::StencilFunc(gl::ALWAYS, value, 0xFF);
+                    gl::StencilOp(gl::KEEP, gl::KEEP, action.gl_enum());
+                    gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+                },
+                (None, Some((compare, value))) => {
+                    gl::Enable(gl::STENCIL_TEST);
+                    gl::StencilFunc(compare.gl_enum(), value, 0xFF);
+                    gl::StencilOp(gl::KEEP, gl::KEEP, gl::KEEP);
+                    gl::ColorMask(r as u8, g as u8, b as u8, a as u8);
+                },
+                (None, None) => {
+                    gl::Disable(gl::STENCIL_TEST);
+                    gl::ColorMask(r as u8, g as u8, b as u8, a as u8);
+                },
+            }
+
+            match self.scissor {
+                Some(scissor) => {
+                    let y = if flipped { scissor.y } else { target_height as i32 - scissor.y - scissor.h };
+                    gl::Enable(gl::SCISSOR_TEST);
+                    gl::Scissor(scissor.x, y, scissor.w, scissor.h);
+                },
+                None => gl::Disable(gl::SCISSOR_TEST),
+            }
+        }
+    }
+}
+
+impl super::Renderer2D {
+    /// Changes the render state, after drawing everything that was batched with the old one.
+    pub fn set_render_state<F: FnOnce(&mut RenderState)>(&mut self, change: F) {
+        self.flush();
+        change(&mut self.render_state);
+        self.apply_render_state();
+    }
+
+    pub fn apply_render_state(&self) {
+        self.render_state.apply(self.target_size().1, !self.canvases.is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+use std::sync::Arc;
+
+use glam::*;
+
+use gl_wrapper::gl_types::{ArrayBuffer, VertexArray};
+use gl_wrapper::mesh::Vertex;
+use gl_wrapper::shader::ShaderProgram;
+
+use mlua::prelude::*;
+use mlua::{UserData, UserDataMethods};
+
+use super::{Image, Quad};
+use super::image::{DrawTransform, quad_vertices};
+use super::batch::transform_vertices;
+
+const VERTICES_PER_SPRITE: usize = 6;
+
+pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
+    methods.add_method("newSpriteBatch", |_, _obj, (image, max_sprites, usage): (Image, Option<usize>, Option<String>)| {
+        let usage = match usage.as_deref() {
+            None | Some("dynamic") => gl::DYNAMIC_DRAW,
+            Some("static") => gl::STATIC_DRAW,
+            Some("stream") => gl::STREAM_DRAW,
+            Some(other) => return Err(LuaError::RuntimeError(format!("Unknown usage `{}`, expected dynamic, static or stream!", other))),
+        };
+        let max_sprites = max_sprites.unwrap_or(1000);
+        if max_sprites == 0 {
+            return Err(LuaError::RuntimeError("A SpriteBatch needs room for at least one sprite!".to_string()));
+        }
+        Ok(SpriteBatch::new(image, max_sprites, usage))
+    });
+}
+
+/// Sprites that all use the same image, kept on the GPU between frames and drawn in a single draw call.
+/// Only sprites that changed get uploaded again.
+/// Sprites are colored with `SpriteBatch:setColor`, and then with `husky.graphics:setColor` when the batch is drawn.
+pub struct SpriteBatch {
+    pub image: Image,
+    max_sprites: usize,
+
+    ///What's on the GPU, or will be after the next upload.
+    vertices: Vec<Vertex>,
+    ///Sprites that changed since the last upload, as a range of sprite indices.
+    dirty: Option<(usize, usize)>,
+    color: (f32, f32, f32, f32),
+    ///The draw color the sprites on the GPU are multiplied by.
+    tint: (f32, f32, f32, f32),
+
+    vbo: ArrayBuffer,
+    vao: VertexArray,
+}
+
+impl SpriteBatch {
+    pub fn new(image: Image, max_sprites: usize, usage: gl::types::GLenum) -> Self {
+        let vbo = ArrayBuffer::new();
+        vbo.bind();
+        vbo.empty_with_length(max_sprites * VERTICES_PER_SPRITE * std::mem::size_of::<Vertex>(), usage);
+        vbo.unbind();
+
+        let vao = VertexArray::new();
+        vao.bind();
+        vbo.bind();
+        vao.attrib_pointers();
+        vbo.unbind();
+        vao.unbind();
+
+        Self {
+            image: image,
+            max_sprites: max_sprites,
+
+            vertices: Vec::with_capacity(max_sprites * VERTICES_PER_SPRITE),
+            dirty: None,
+            color: (1.0, 1.0, 1.0, 1.0),
+            tint: (1.0, 1.0, 1.0, 1.0),
+
+            vbo: vbo,
+            vao: vao,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.vertices.len() / VERTICES_PER_SPRITE
+    }
+
+    fn sprite_vertices(&self, quad: Option<&Quad>, t: &DrawTransform) -> Vec<Vertex> {
+        let (w, h) = match quad {
+            Some(quad) => (quad.w, quad.h),
+            None => (self.image.dimensions().0 as f32, self.image.dimensions().1 as f32),
+        };
+        let uv_rect = quad.map_or(((0.0, 0.0), (1.0, 1.0)), |quad| quad.uv_rect());
+        let model = t.matrix() * Mat4::from_scale(vec3(w, h, 1.0));
+        transform_vertices(&quad_vertices(uv_rect), model, self.color).collect()
+    }
+
+    fn mark_dirty(&mut self, index: usize) {
+        self.dirty = Some(match self.dirty {
+            Some((start, end)) => (start.min(index), end.max(index + 1)),
+            None => (index, index + 1),
+        });
+    }
+
+    /// Returns the index of the new sprite.
+    fn add(&mut self, quad: Option<&Quad>, t: &DrawTransform) -> LuaResult<usize> {
+        let index = self.count();
+        if index >= self.max_sprites {
+            return Err(LuaError::RuntimeError(format!("SpriteBatch is full, it only has room for {} sprites!", self.max_sprites)));
+        }
+        let vertices = self.sprite_vertices(quad, t);
+        self.vertices.extend(vertices);
+        self.mark_dirty(index);
+        Ok(index)
+    }
+
+    fn set(&mut self, index: usize, quad: Option<&Quad>, t: &DrawTransform) -> LuaResult<()> {
+        if index >= self.count() {
+            return Err(LuaError::RuntimeError(format!("This SpriteBatch only has {} sprites!", self.count())));
+        }
+        let vertices = self.sprite_vertices(quad, t);
+        let start = index * VERTICES_PER_SPRITE;
+        self.vertices[start..start + VERTICES_PER_SPRITE].copy_from_slice(&vertices);
+        self.mark_dirty(index);
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.vertices.clear();
+        self.dirty = None;
+    }
+
+    /// Uploads every sprite that changed since the last upload.
+    pub fn flush(&mut self) {
+        if let Some((start, end)) = self.dirty.take() {
+            let vertices: Vec<Vertex> = transform_vertices(&self.vertices[start * VERTICES_PER_SPRITE..end * VERTICES_PER_SPRITE], Mat4::IDENTITY, self.tint).collect();
+            self.vbo.bind();
+            self.vbo.sub_data(&vertices, (start * VERTICES_PER_SPRITE * std::mem::size_of::<Vertex>()) as isize);
+            self.vbo.unbind();
+        }
+    }
+
+    /// Uploads the sprites multiplied by the draw color. A different color than last time uploads every sprite again.
+    pub fn flush_tinted(&mut self, tint: (f32, f32, f32, f32)) {
+        if tint != self.tint {
+            self.tint = tint;
+            if self.count() > 0 {
+                self.dirty = Some((0, self.count()));
+            }
+        }
+        self.flush();
+    }
+}
+
+impl UserData for SpriteBatch {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        //add([quad], x, y, r, sx, sy, ox, oy), returns the index of the sprite
+        methods.add_method_mut("add", |lua, obj, args: LuaMultiValue| {
+            let (quad, transform) = DrawTransform::from_lua_args(lua, args)?;
+            Ok(obj.add(quad.as_ref(), &transform)? + 1)
+        });
+        methods.add_method_mut("set", |lua, obj, (index, args): (usize, LuaMultiValue)| {
+            //Indices from Lua start at 1
+            let sprite = index.checked_sub(1)
+                .filter(|&sprite| sprite < obj.count())
+                .ok_or_else(|| LuaError::RuntimeError(format!("There's no sprite with index {} in this SpriteBatch!", index)))?;
+            let (quad, transform) = DrawTransform::from_lua_args(lua, args)?;
+            obj.set(sprite, quad.as_ref(), &transform)
+        });
+        methods.add_method_mut("clear", |_, obj, ()| {
+            obj.clear();
+            Ok(())
+        });
+        methods.add_method_mut("flush", |_, obj, ()| {
+            obj.flush();
+            Ok(())
+        });
+
+        //Applies to sprites added or set after this
+        methods.add_method_mut("setColor", |_, obj, (r,g,b,a): (f32, f32, f32, Option<f32>)| {
+            obj.color = (r,g,b,a.unwrap_or(1.0));
+            Ok(())
+        });
+        methods.add_method("getColor", |_, obj, ()| Ok(obj.color));
+
+        methods.add_method("getCount", |_, obj, ()| Ok(obj.count()));
+        methods.add_method("getBufferSize", |_, obj, ()| Ok(obj.max_sprites));
+        methods.add_method("getImage", |_, obj, ()| Ok(obj.image.clone()));
+    }
+}
+
+impl super::Renderer2D {
+    pub fn sprite_batch(&mut self, shader: &Arc<ShaderProgram>, color: (f32, f32, f32, f32), sprite_batch: &mut SpriteBatch, t: &DrawTransform) {
+        sprite_batch.flush_tinted(color);
+        if sprite_batch.count() == 0 { return; }
+
+        //Draws outside the batch, so whatever is batched has to go first
+        self.flush();
+
+        let vertex_count = sprite_batch.count() * VERTICES_PER_SPRITE;
+        shader.bind();
+        shader.uniform("mvp", self.projection() * self.transform * t.matrix());
+        unsafe { gl::ActiveTexture(gl::TEXTURE0); }
+        sprite_batch.image.texture.bind();
+        sprite_batch.vao.bind();
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, vertex_count as i32);
+        }
+        sprite_batch.vao.unbind();
+        sprite_batch.image.texture.unbind();
+        shader.unbind();
+
+        self.batch.stats.draw_calls += 1;
+        self.batch.stats.vertices += vertex_count as u32;
+    }
+}
@@ -0,0 +1,139 @@
+use mlua::prelude::*;
+use mlua::UserDataMethods;
+
+pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
+    //stencil(fn, [action, value, keepvalues]), whatever fn draws writes to the stencil buffer instead of the screen
+    methods.add_method("stencil", |_, obj, (draw, action, value, keep_values): (LuaFunction, Option<String>, Option<i32>, Option<bool>)| {
+        let action = match action {
+            Some(action) => StencilAction::from_str(&action)?,
+            None => StencilAction::Replace,
+        };
+        let value = value.unwrap_or(1);
+
+        {
+            let mut renderer = obj.get_lock();
+            if renderer.renderer2d.render_state.stencil_write.is_some() {
+                return Err(LuaError::RuntimeError("Can't call stencil from inside a stencil function!".to_string()));
+            }
+            renderer.renderer2d.set_render_state(|state| state.stencil_write = Some((action, value)));
+            if !keep_values.unwrap_or(false) {
+                unsafe {
+                    gl::ClearStencil(0);
+                    gl::Clear(gl::STENCIL_BUFFER_BIT);
+                }
+            }
+        }
+
+        //The lock can't be held here, as the function draws through husky.graphics too
+        let result = draw.call::<_, ()>(());
+        obj.get_lock().renderer2d.set_render_state(|state| state.stencil_write = None);
+        result
+    });
+
+    //setStencilTest(compare, value) only draws where `value compare stencil` holds, setStencilTest() turns it off
+    methods.add_method("setStencilTest", |_, obj, (compare, value): (Option<String>, Option<i32>)| {
+        let test = match compare {
+            Some(compare) => Some((StencilCompare::from_str(&compare)?, value.unwrap_or(0))),
+            None => None,
+        };
+        obj.get_lock().renderer2d.set_render_state(|state| state.stencil_test = test);
+        Ok(())
+    });
+    methods.add_method("getStencilTest", |_, obj, ()| {
+        match obj.get_lock().renderer2d.render_state.stencil_test {
+            Some((compare, value)) => Ok((compare.as_str(), value)),
+            None => Ok(("always", 0)),
+        }
+    });
+}
+
+/// What happens to the stencil value of every pixel that gets drawn inside `stencil`.
+#[derive(Clone, Copy)]
+pub enum StencilAction {
+    Replace,
+    Increment,
+    Decrement,
+    IncrementWrap,
+    DecrementWrap,
+    Invert,
+}
+
+impl StencilAction {
+    fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "replace" => Ok(Self::Replace),
+            "increment" => Ok(Self::Increment),
+            "decrement" => Ok(Self::Decrement),
+            "incrementwrap" => Ok(Self::IncrementWrap),
+            "decrementwrap" => Ok(Self::DecrementWrap),
+            "invert" => Ok(Self::Invert),
+            _ => Err(LuaError::RuntimeError(format!("Unknown stencil action `{}`, expected replace, increment, decrement, incrementwrap, decrementwrap or invert!", s))),
+        }
+    }
+
+    pub fn gl_enum(&self) -> gl::types::GLenum {
+        match self {
+            Self::Replace => gl::REPLACE,
+            Self::Increment => gl::INCR,
+            Self::Decrement => gl::DECR,
+            Self::IncrementWrap => gl::INCR_WRAP,
+            Self::DecrementWrap => gl::DECR_WRAP,
+            Self::Invert => gl::INVERT,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum StencilCompare {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Never,
+    Always,
+}
+
+impl StencilCompare {
+    fn from_str(s: &str) -> LuaResult<Self> {
+        match s {
+            "equal" => Ok(Self::Equal),
+            "notequal" => Ok(Self::NotEqual),
+            "less" => Ok(Self::Less),
+            "lequal" => Ok(Self::LessEqual),
+            "greater" => Ok(Self::Greater),
+            "gequal" => Ok(Self::GreaterEqual),
+            "never" => Ok(Self::Never),
+            "always" => Ok(Self::Always),
+            _ => Err(LuaError::RuntimeError(format!("Unknown stencil compare mode `{}`, expected equal, notequal, less, lequal, greater, gequal, never or always!", s))),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Equal => "equal",
+            Self::NotEqual => "notequal",
+            Self::Less => "less",
+            Self::LessEqual => "lequal",
+            Self::Greater => "greater",
+            Self::GreaterEqual => "gequal",
+            Self::Never => "never",
+            Self::Always => "always",
+        }
+    }
+
+    /// GL compares the reference value against the stored one, the same way around as `setStencilTest` does.
+    pub fn gl_enum(&self) -> gl::types::GLenum {
+        match self {
+            Self::Equal => gl::EQUAL,
+            Self::NotEqual => gl::NOTEQUAL,
+            Self::Less => gl::LESS,
+            Self::LessEqual => gl::LEQUAL,
+            Self::Greater => gl::GREATER,
+            Self::GreaterEqual => gl::GEQUAL,
+            Self::Never => gl::NEVER,
+            Self::Always => gl::ALWAYS,
+        }
+    }
+}
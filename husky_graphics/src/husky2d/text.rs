@@ -1,39 +1,179 @@
-use image::Rgba;
-use rusttype::{Font, Scale, point};
+use std::sync::Arc;
+
+use glam::*;
+
+use rusttype::gpu_cache::{Cache, CacheWriteErr};
+
+use gl_wrapper::gl_types::Texture;
+use gl_wrapper::mesh::Vertex;
+use gl_wrapper::shader::ShaderProgram;
+
+use mlua::prelude::*;
+use mlua::UserDataMethods;
+
+use super::batch::{BatchState, transform_vertices};
+use super::image::DrawTransform;
+use super::font::{Font, ColoredText, TextAlign, LaidOutGlyph};
+
+const INITIAL_ATLAS_SIZE: u32 = 512;
+/// Text drawn bigger than this gets stretched instead of rasterized at full size, so a few huge glyphs can't fill the atlas.
+const MAX_RASTER_SIZE: f32 = 256.0;
+
+pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
+    //print(text, x, y, r, sx, sy, ox, oy), text can also be a table of colors and strings
+    methods.add_method("print", |lua, obj, (text, args): (LuaValue, LuaMultiValue)| {
+        let text = ColoredText::from_lua(text)?;
+        let (_, transform) = DrawTransform::from_lua_args(lua, args)?;
+        let mut renderer = obj.get_lock();
+        let font = renderer.active_font.clone();
+        let color = renderer.active_color;
+        let shader = renderer.get_active_shader().raw_program.clone();
+        renderer.renderer2d.print(&shader, color, &font, &text, None, &transform);
+        Ok(())
+    });
+
+    //printf(text, x, y, limit, [align], r, sx, sy, ox, oy) wraps lines wider than limit, align is left by default
+    methods.add_method("printf", |lua, obj, (text, x, y, limit, align, args): (LuaValue, f32, f32, f32, Option<String>, LuaMultiValue)| {
+        let text = ColoredText::from_lua(text)?;
+        let align = match align {
+            Some(align) => TextAlign::from_str(&align)?,
+            None => TextAlign::Left,
+        };
+        let mut transform_args = vec![LuaValue::Number(x as f64), LuaValue::Number(y as f64)];
+        transform_args.extend(args);
+        let (_, transform) = DrawTransform::from_lua_args(lua, LuaMultiValue::from_vec(transform_args))?;
+
+        let mut renderer = obj.get_lock();
+        let font = renderer.active_font.clone();
+        let color = renderer.active_color;
+        let shader = renderer.get_active_shader().raw_program.clone();
+        renderer.renderer2d.print(&shader, color, &font, &text, Some((limit, align)), &transform);
+        Ok(())
+    });
+}
+
+/// A texture that glyphs get rasterized into the first time they're drawn, so text can go through the batch like images do.
+pub struct GlyphAtlas {
+    cache: Cache<'static>,
+    size: u32,
+    pub texture: Arc<Texture>,
+}
+
+impl GlyphAtlas {
+    pub fn new() -> Self {
+        Self {
+            cache: Cache::builder().dimensions(INITIAL_ATLAS_SIZE, INITIAL_ATLAS_SIZE).build(),
+            size: INITIAL_ATLAS_SIZE,
+            texture: Self::create_texture(INITIAL_ATLAS_SIZE),
+        }
+    }
+
+    fn create_texture(size: u32) -> Arc<Texture> {
+        let texture = Texture::new((size as i32, size as i32), &vec![0u8; (size * size) as usize], gl::R8 as i32, gl::RED, gl::UNSIGNED_BYTE);
+        //Only coverage is stored, which turns into white with that coverage as alpha so the default shader can tint it
+        texture.set_swizzle([gl::ONE, gl::ONE, gl::ONE, gl::RED]);
+        texture.set_filter(gl::LINEAR, gl::LINEAR);
+        texture.set_wrap(gl::CLAMP_TO_EDGE, gl::CLAMP_TO_EDGE);
+        Arc::new(texture)
+    }
+
+    /// Doubles the size of the atlas, which throws away every cached glyph.
+    fn grow(&mut self) {
+        self.size *= 2;
+        self.cache.to_builder().dimensions(self.size, self.size).rebuild(&mut self.cache);
+        self.texture = Self::create_texture(self.size);
+        debug!("Glyph atlas grew to {}x{}", self.size, self.size);
+    }
+}
 
 impl super::Renderer2D {
-    pub fn gfx_print(&mut self, color: (f32, f32, f32, f32), font: &Font, text: &str, xoff: f32, yoff: f32) {
-        let font_size = 24.0; //TODO: Don't hardcode this???? Also copy the line below to wherever you start passing this to calculate it properly
-        // let scale = (font_size * window_ctx.window().scale_factor() as f32).round();
-        let scale = Scale::uniform(font_size);
-
-        let font_colour = ((color.0 * 255.0) as u8, (color.1 * 255.0) as u8, (color.2 * 255.0) as u8);
-
-        let v_metrics = font.v_metrics(scale);
-
-        let glyphs: Vec<_> = font.layout(text, scale, point(0f32, v_metrics.ascent)).collect();
-
-        {
-            let mut image_lock = self.font_image.lock().unwrap();
-            for glyph in glyphs {
-                if let Some(bounding_box) = glyph.pixel_bounding_box() {
-                    // Draw the glyph into the image per-pixel by using the draw closure
-                    glyph.draw(|x, y, v| {
-                        if (x + xoff as u32 + bounding_box.min.x as u32) < image_lock.width() && (y + yoff as u32 + bounding_box.min.y as u32) < image_lock.height() {
-                            image_lock.put_pixel(
-                                // Offset the position by the glyph bounding box
-                                x + xoff as u32 + bounding_box.min.x as u32,
-                                y + yoff as u32 + bounding_box.min.y as u32,
-                                // Turn the coverage into an alpha value
-                                Rgba([font_colour.0, font_colour.1, font_colour.2, (v * 255.0) as u8]),
-                            )
-                        }
-                    });
-                }
-            }
+    /// Draws `text` with its top left at the origin of `t`.
+    /// When `wrap` is given, lines are wrapped to the limit and aligned within it.
+    pub fn print(&mut self, shader: &Arc<ShaderProgram>, color: (f32, f32, f32, f32), font: &Font, text: &ColoredText, wrap: Option<(f32, TextAlign)>, t: &DrawTransform) {
+        //Rasterize at the size the text ends up on screen, so scaled up text stays sharp
+        let raster_scale = (self.transform_scale() * (t.sx * t.sy).abs().sqrt()).min(MAX_RASTER_SIZE / font.size()).max(0.01);
+        let glyphs = match wrap {
+            Some((limit, align)) => font.layout_wrapped(text, limit, align, raster_scale),
+            None => font.layout(text, raster_scale),
+        };
+        self.cache_glyphs(&glyphs);
+
+        let mut vertices = Vec::with_capacity(glyphs.len() * 6);
+        for glyph in &glyphs {
+            let (uv, rect) = match self.glyph_atlas.cache.rect_for(glyph.face_id, &glyph.glyph) {
+                Ok(Some(rects)) => rects,
+                _ => continue,
+            };
+            let corner = |x: i32, y: i32, u: f32, v: f32| Vertex {
+                pos: (x as f32 / raster_scale, y as f32 / raster_scale, 0.0).into(),
+                uv: (u, v).into(),
+                rgba: glyph.color.into(),
+            };
+            let top_left = corner(rect.min.x, rect.min.y, uv.min.x, uv.min.y);
+            let top_right = corner(rect.max.x, rect.min.y, uv.max.x, uv.min.y);
+            let bottom_right = corner(rect.max.x, rect.max.y, uv.max.x, uv.max.y);
+            let bottom_left = corner(rect.min.x, rect.max.y, uv.min.x, uv.max.y);
+            vertices.extend_from_slice(&[top_left, top_right, bottom_right, bottom_left, top_left, bottom_right]);
         }
 
-        self.print_count += 1;
+        let state = BatchState {
+            shader: shader.clone(),
+            texture: self.glyph_atlas.texture.clone(),
+            primitive: gl::TRIANGLES,
+        };
+        let projection = self.projection();
+        let transform = self.transform * t.matrix();
+        self.batch.push(&state, projection, transform_vertices(&vertices, transform, color));
+    }
+
+    /// Prints at a position on screen, ignoring the transform and render state the game set.
+    /// For things drawn on top of the game, like the FPS counter.
+    pub fn print_overlay(&mut self, shader: &Arc<ShaderProgram>, color: (f32, f32, f32, f32), font: &Font, text: &str, x: f32, y: f32) {
+        self.flush();
+        let render_state = std::mem::take(&mut self.render_state);
+        let transform = std::mem::replace(&mut self.transform, Mat4::IDENTITY);
+        self.apply_render_state();
 
+        let t = DrawTransform { x: x, y: y, r: 0.0, sx: 1.0, sy: 1.0, ox: 0.0, oy: 0.0 };
+        self.print(shader, color, font, &ColoredText::plain(text), None, &t);
+
+        self.flush();
+        self.render_state = render_state;
+        self.transform = transform;
+        self.apply_render_state();
+    }
+
+    /// Makes sure every glyph is in the atlas, growing it if they don't fit.
+    fn cache_glyphs(&mut self, glyphs: &[LaidOutGlyph]) {
+        for glyph in glyphs {
+            self.glyph_atlas.cache.queue_glyph(glyph.face_id, glyph.glyph.clone());
+        }
+
+        let projection = self.projection();
+        loop {
+            let batch = &mut self.batch;
+            let texture = &self.glyph_atlas.texture;
+            let mut flushed = false;
+            let result = self.glyph_atlas.cache.cache_queued(|rect, data| {
+                //Uploading can overwrite glyphs that are batched but not drawn yet, so draw them first
+                if !flushed {
+                    batch.flush(projection);
+                    flushed = true;
+                }
+                texture.sub_data((rect.min.x as i32, rect.min.y as i32), (rect.width() as i32, rect.height() as i32), data);
+            });
+            match result {
+                Ok(_) => return,
+                Err(CacheWriteErr::NoRoomForWholeQueue) if self.glyph_atlas.size * 2 <= *super::MAX_IMAGE_DIMENSION => {
+                    //Glyphs from the old atlas might still be waiting in the batch
+                    self.batch.flush(projection);
+                    self.glyph_atlas.grow();
+                },
+                Err(e) => {
+                    warn!("Failed to fit text into the glyph atlas: {}", e);
+                    return;
+                },
+            }
+        }
     }
 }
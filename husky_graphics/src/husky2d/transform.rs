@@ -0,0 +1,92 @@
+use glam::*;
+
+use mlua::prelude::*;
+use mlua::UserDataMethods;
+
+/// How deep `push` can go, so a missing `pop` shows up as an error instead of eating memory.
+const MAX_STACK_DEPTH: usize = 64;
+
+pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
+    methods.add_method("push", |_, obj, ()| {
+        let mut renderer = obj.get_lock();
+        let renderer2d = &mut renderer.renderer2d;
+        if renderer2d.transform_stack.len() >= MAX_STACK_DEPTH {
+            return Err(LuaError::RuntimeError(format!("Transform stack overflow, push was called more than {} times without a pop!", MAX_STACK_DEPTH)));
+        }
+        let transform = renderer2d.transform;
+        renderer2d.transform_stack.push(transform);
+        Ok(())
+    });
+
+    methods.add_method("pop", |_, obj, ()| {
+        let mut renderer = obj.get_lock();
+        let renderer2d = &mut renderer.renderer2d;
+        renderer2d.transform = renderer2d.transform_stack.pop().ok_or_else(|| LuaError::RuntimeError("Transform stack underflow, pop was called without a push!".to_string()))?;
+        Ok(())
+    });
+
+    methods.add_method("origin", |_, obj, ()| {
+        obj.get_lock().renderer2d.transform = Mat4::IDENTITY;
+        Ok(())
+    });
+
+    methods.add_method("translate", |_, obj, (dx, dy): (f32, f32)| {
+        obj.get_lock().renderer2d.apply_transform(Mat4::from_translation(vec3(dx, dy, 0.0)));
+        Ok(())
+    });
+
+    methods.add_method("rotate", |_, obj, angle: f32| {
+        obj.get_lock().renderer2d.apply_transform(Mat4::from_rotation_z(angle));
+        Ok(())
+    });
+
+    methods.add_method("scale", |_, obj, (sx, sy): (f32, Option<f32>)| {
+        obj.get_lock().renderer2d.apply_transform(Mat4::from_scale(vec3(sx, sy.unwrap_or(sx), 1.0)));
+        Ok(())
+    });
+
+    methods.add_method("shear", |_, obj, (kx, ky): (f32, f32)| {
+        let shear = Mat4::from_cols(
+            vec4(1.0, ky, 0.0, 0.0),
+            vec4(kx, 1.0, 0.0, 0.0),
+            vec4(0.0, 0.0, 1.0, 0.0),
+            vec4(0.0, 0.0, 0.0, 1.0),
+        );
+        obj.get_lock().renderer2d.apply_transform(shear);
+        Ok(())
+    });
+
+    //From the coordinates you draw with to pixels on screen
+    methods.add_method("transformPoint", |_, obj, (x, y): (f32, f32)| {
+        Ok(obj.get_lock().renderer2d.transform_point(x, y))
+    });
+
+    //From pixels on screen back to the coordinates you draw with, for things like mouse picking
+    methods.add_method("inverseTransformPoint", |_, obj, (x, y): (f32, f32)| {
+        let point = obj.get_lock().renderer2d.transform.inverse().transform_point3(vec3(x, y, 0.0));
+        Ok((point.x, point.y))
+    });
+}
+
+impl super::Renderer2D {
+    /// Applies `transform` before the current one, like every transform call from Lua does.
+    fn apply_transform(&mut self, transform: Mat4) {
+        self.transform = self.transform * transform;
+    }
+
+    pub fn transform_point(&self, x: f32, y: f32) -> (f32, f32) {
+        let point = self.transform.transform_point3(vec3(x, y, 0.0));
+        (point.x, point.y)
+    }
+
+    /// How much the transform scales areas by, as a single factor for things like line width.
+    pub fn transform_scale(&self) -> f32 {
+        self.transform.x_axis.truncate().truncate().perp_dot(self.transform.y_axis.truncate().truncate()).abs().sqrt()
+    }
+
+    /// Every frame starts out untransformed.
+    pub(super) fn reset_transform(&mut self) {
+        self.transform = Mat4::IDENTITY;
+        self.transform_stack.clear();
+    }
+}
@@ -12,7 +12,8 @@ use super::gpu_repr;
 
 pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
     methods.add_method("drawVoxelScene", |_, obj, scene: SceneGuard| {
-        let renderer = obj.get_lock();
+        let mut renderer = obj.get_lock();
+        renderer.flush();
         renderer.voxel_renderer.draw_scene(scene);
         Ok(())
     });
@@ -33,7 +34,7 @@ impl VoxelRenderer {
 
         let raymarch_src = include_str!("../../../shaders/raymarch.glsl");
         let raymarch_shader = GlShader::from_source(raymarch_src, gl::COMPUTE_SHADER).expect("Failed to compile shader!");
-        let shader = Shader::from_shaders(vec![&raymarch_shader]);
+        let shader = Shader::from_shaders(vec![&raymarch_shader]).expect("Failed to link shader!");
 
         let render_texture = Texture::from_ptr((1280, 720), std::ptr::null(), gl::RGBA32F as i32, gl::RGBA, gl::FLOAT);
 
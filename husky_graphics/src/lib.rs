@@ -3,16 +3,15 @@
 
 use std::sync::MutexGuard;
 use std::sync::atomic::{Ordering, AtomicBool};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-
-use rusttype::Font;
+use std::sync::{Arc, Mutex, Weak};
 
 use mlua::prelude::*;
 use mlua::{Table, UserData, UserDataMethods};
 
 use gl_wrapper::shader::Shader as GlShader;
 
+use husky_fs::GameFs;
+
 pub mod husky2d;
 pub mod husky3d;
 
@@ -24,90 +23,66 @@ lazy_static! {
 }
 
 pub struct Renderer {
-    pub fonts: HashMap<String, Font<'static>>,
-    pub working_directory: String,
+    ///Used by the engine itself, and by `newFont` without a path.
+    pub default_font: husky2d::Font,
+    pub active_font: husky2d::Font,
+    ///Every asset the game loads goes through this.
+    pub fs: GameFs,
 
     pub renderer2d: husky2d::Renderer2D,
     pub voxel_renderer: husky3d::voxel::VoxelRenderer,
 
     pub active_color: (f32, f32, f32, f32),
 
-    ///Lets us know if the shader is actually already bound.
-    //This is probably redundant to store, but I usually code at 3 am
-    //so I can't think if it is or not.
-    is_default_shader_bound: bool,
-    ///Lets us know if the shader is actually already bound.
-    is_shader_bound: bool,
+    //Shaders get bound by the 2D batch when it flushes
     pub default_shader: Shader,
     pub active_shader: Option<Shader>,
 }
 
 impl Renderer {
-    pub fn new(working_directory: String) -> Self {
-        let roboto = Font::try_from_bytes(include_bytes!("../../fonts/RobotoMono-Regular.ttf") as &[u8]).expect("Failed to load font!");
-        let mut fonts = HashMap::new();
-        fonts.insert("roboto".to_string(), roboto);
+    pub fn new(fs: GameFs) -> Self {
+        let roboto = husky2d::FontFace::from_bytes(include_bytes!("../../fonts/RobotoMono-Regular.ttf").to_vec()).expect("Failed to load font!");
+        let default_font = husky2d::Font::new(roboto, husky2d::DEFAULT_FONT_SIZE);
 
         let default_shader_vs = GlShader::from_source(include_str!("../../shaders/default_vs.glsl"), gl::VERTEX_SHADER).expect("Failed to compile default vs shader!");
         let default_shader_fs = GlShader::from_source(include_str!("../../shaders/default_fs.glsl"), gl::FRAGMENT_SHADER).expect("Failed to compile default fs shader!");
-        let default_shader = Shader::from_shaders(vec![&default_shader_vs, &default_shader_fs]);
+        let default_shader = Shader::from_shaders(vec![&default_shader_vs, &default_shader_fs]).expect("Failed to link default shader!");
 
         Self {
-            fonts: fonts,
-            working_directory: working_directory,
+            default_font: default_font.clone(),
+            active_font: default_font,
+            fs: fs,
 
-            renderer2d: husky2d::Renderer2D::new("roboto".to_string()),
+            renderer2d: husky2d::Renderer2D::new(),
             voxel_renderer: husky3d::voxel::VoxelRenderer::new(),
 
             active_color: (1.0, 1.0, 1.0, 1.0),
 
-            is_default_shader_bound: false,
-            is_shader_bound: false,
             default_shader: default_shader,
             active_shader: None,
         }
     }
 
     fn set_active_shader(&mut self, shader_opt: Option<Shader>) {
-        match shader_opt {
-            Some(shader) => {
-                shader.raw_program.bind();
-                self.active_shader = Some(shader);
-                self.is_shader_bound = true;
-                self.is_default_shader_bound = false;
-            },
-            None => {
-                self.default_shader.raw_program.bind();
-                self.active_shader = None;
-                self.is_shader_bound = false;
-                self.is_default_shader_bound = true;
-            }
-        }
+        self.active_shader = shader_opt;
     }
 
-    fn get_active_shader(&mut self) -> &Shader {
-        if self.is_default_shader_bound { return &self.default_shader; }
-        if self.is_shader_bound { return self.active_shader.as_ref().unwrap(); }
-
-        //No shader is already bound, so we have to figure out which one to bind
-        match &self.active_shader {
-            Some(shader) => {
-                self.is_shader_bound = true;
-                shader.raw_program.bind();
-                shader
-            },
-            None => {
-                self.default_shader.raw_program.bind();
-                self.is_default_shader_bound = true;
-                &self.default_shader
-            }
-        }
+    fn get_active_shader(&self) -> &Shader {
+        self.active_shader.as_ref().unwrap_or(&self.default_shader)
     }
 
-    pub fn clear(&self, r: f32, g: f32, b: f32, a: f32) {
+    /// Draws everything that's been batched up so far.
+    pub fn flush(&mut self) {
+        self.renderer2d.flush();
+    }
+
+    pub fn clear(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        //Anything drawn before the clear has to actually be drawn before it
+        self.flush();
         unsafe {
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
             gl::ClearColor(r,g,b,a);
+            gl::ClearStencil(0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
         }
     }
 
@@ -115,31 +90,21 @@ impl Renderer {
         self.active_color = (r,g,b,a);
     }
 
+    /// Prints with the default font and shader, on top of whatever the game drew.
+    pub fn print_overlay(&mut self, color: (f32, f32, f32, f32), text: &str, x: f32, y: f32) {
+        let font = self.default_font.clone();
+        let shader = self.default_shader.raw_program.clone();
+        self.renderer2d.print_overlay(&shader, color, &font, text, x, y);
+    }
+
     pub fn begin_frame(&self) {
-        unsafe {
-            let win_size = WINDOW_SIZE.lock().unwrap();
-            gl::Viewport(0,0, win_size.0 as i32, win_size.1 as i32);
-        }
+        self.renderer2d.apply_viewport();
+        self.renderer2d.apply_render_state();
     }
 
     pub fn finish_frame(&mut self) {
-        //TODO: Start using your own setShader functions, to avoid code repetition
-        if self.is_default_shader_bound {
-            self.default_shader.raw_program.unbind();
-            self.is_default_shader_bound = false;
-        }
-        if self.is_shader_bound {
-            match &self.active_shader {
-                Some(shader) => {
-                    shader.raw_program.unbind();
-                    self.active_shader = None;
-                    self.is_shader_bound = false;
-                },
-                None => panic!("Shader bound but not in memory!")
-            }
-        }
-
         self.renderer2d.finish_frame();
+        self.active_shader = None;
     }
 }
 
@@ -149,15 +114,19 @@ pub struct RendererGuard {
 }
 
 impl RendererGuard {
-    pub fn new(working_directory: String) -> Self {
+    pub fn new(fs: GameFs) -> Self {
         Self {
-            renderer: Arc::new(Mutex::new(Renderer::new(working_directory)))
+            renderer: Arc::new(Mutex::new(Renderer::new(fs)))
         }
     }
 
     pub fn get_lock(&self) -> MutexGuard<Renderer> {
         self.renderer.lock().expect("Failed to acquire lock on renderer!")
     }
+
+    fn downgrade(&self) -> Weak<Mutex<Renderer>> {
+        Arc::downgrade(&self.renderer)
+    }
 }
 
 impl UserData for RendererGuard {
@@ -186,15 +155,6 @@ impl UserData for RendererGuard {
             Ok(win_size)
         });
 
-        //TODO: Abstract to husky2d/text.rs
-        methods.add_method("print", |_, obj, (text, x,y): (String, f32,f32)| {
-            let mut renderer = obj.get_lock();
-            let font = renderer.fonts.get("roboto").unwrap().clone();
-            let color = renderer.active_color;
-            renderer.renderer2d.gfx_print(color, &font, &text, x,y);
-            Ok(())
-        });
-
         shader_wrapper::add_methods(methods);
         husky2d::add_methods(methods);
         husky3d::add_methods(methods);
@@ -1,46 +1,75 @@
+use std::ffi::CString;
 use std::collections::HashMap;
-use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Weak};
 
-use gl_wrapper::gl_types::UniformValue;
 use gl_wrapper::shader::{Shader as GlShader, ShaderProgram as GlShaderProgram};
 use gl::types::*;
 
+use husky_fs::GameFs;
+
 use mlua::prelude::{LuaResult, LuaValue, LuaError};
 use mlua::{UserData, UserDataMethods, Error};
 
+use crate::husky2d::{Image, Canvas};
+
 pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods: &mut M) {
-    methods.add_method("newShader", |_, obj, (path_vs, path_gs, path_fs): (String, Option<String>, Option<String>)| {
-        let wd_str = {
+    //newShader(fragment), newShader(vertex, fragment) or newShader(vertex, geometry, fragment), all paths to GLSL files.
+    //Vertex shaders get `vec3 Position`, `vec2 UV` and `vec4 Color` at locations 0, 1 and 2, and `uniform mat4 mvp`.
+    //Fragment shaders get `in VS_OUTPUT { vec3 Color; vec2 UV; } IN` and `in float Alpha` from the default vertex shader,
+    //and the texture being drawn through `uniform sampler2D tex`, which is plain white for shapes.
+    //The draw color is part of the vertex color now, `uniform vec4 drawColor` is deprecated and always white
+    methods.add_method("newShader", |_, obj, (path_a, path_b, path_c): (String, Option<String>, Option<String>)| {
+        let fs = {
             let renderer = obj.get_lock();
-            renderer.working_directory.clone()
+            renderer.fs.clone()
+        };
+        let sources = match (path_b, path_c) {
+            (None, None) => vec![ShaderSource::default_vertex(), ShaderSource::read(&fs, &path_a, gl::FRAGMENT_SHADER)?],
+            (Some(path_fs), None) => vec![ShaderSource::read(&fs, &path_a, gl::VERTEX_SHADER)?, ShaderSource::read(&fs, &path_fs, gl::FRAGMENT_SHADER)?],
+            (Some(path_gs), Some(path_fs)) => vec![
+                ShaderSource::read(&fs, &path_a, gl::VERTEX_SHADER)?,
+                ShaderSource::read(&fs, &path_gs, gl::GEOMETRY_SHADER)?,
+                ShaderSource::read(&fs, &path_fs, gl::FRAGMENT_SHADER)?,
+            ],
+            (None, Some(_)) => return Err(Error::RuntimeError("newShader expects a fragment shader, after the vertex and geometry shaders if there are any!".to_string())),
+        };
+        let shaders = compile_sources(&sources).map_err(Error::RuntimeError)?;
+        let shader = Shader::from_shaders(shaders.iter().collect()).map_err(|log| Error::RuntimeError(link_error(&sources, &log)))?;
+        Ok(shader.with_renderer(obj))
+    });
+
+    //validateShader(code, [stage]) returns true, or false and the errors, without creating a shader.
+    //Code can also be the path to a file, stage is vertex, geometry or fragment (the default)
+    methods.add_method("validateShader", |_, obj, (code, stage): (String, Option<String>)| {
+        let kind = match stage.as_deref() {
+            None | Some("fragment") => gl::FRAGMENT_SHADER,
+            Some("vertex") => gl::VERTEX_SHADER,
+            Some("geometry") => gl::GEOMETRY_SHADER,
+            Some(stage) => return Err(Error::RuntimeError(format!("Unknown shader stage `{}`, expected vertex, geometry or fragment!", stage))),
+        };
+        let fs = obj.get_lock().fs.clone();
+        let source = if fs.is_file(&code) {
+            ShaderSource::read(&fs, &code, kind)?
+        } else {
+            ShaderSource { kind: kind, name: "code".to_string(), code: code }
         };
-        let wd = Path::new(&wd_str);
-        if path_gs.is_some() && path_fs.is_none() {
-            //Vertex, fragment, none
-            let vs_src = std::fs::read_to_string(wd.join(path_vs))?;
-            let shader_vs = GlShader::from_source(&vs_src, gl::VERTEX_SHADER).map_err(|_| Error::RuntimeError("Failed to compile vertex shader!".to_string()) )?;
-            let fs_src = std::fs::read_to_string(wd.join(path_gs.unwrap()))?;
-            let shader_fs = GlShader::from_source(&fs_src, gl::FRAGMENT_SHADER).map_err(|_| Error::RuntimeError("Failed to compile fragment shader!".to_string()) )?;
-            return Ok(Shader::from_shaders(vec![&shader_vs, &shader_fs]));
-        } else if path_gs.is_some() && path_fs.is_some() {
-            //Vertex, geom, fragment
-            let vs_src = std::fs::read_to_string(wd.join(path_vs))?;
-            let shader_vs = GlShader::from_source(&vs_src, gl::VERTEX_SHADER).map_err(|_| Error::RuntimeError("Failed to compile vertex shader!".to_string()) )?;
-            let gs_src = std::fs::read_to_string(wd.join(path_gs.unwrap()))?;
-            let shader_gs = GlShader::from_source(&gs_src, gl::GEOMETRY_SHADER).map_err(|_| Error::RuntimeError("Failed to compile fragment shader!".to_string()) )?;
-            let fs_src = std::fs::read_to_string(wd.join(path_fs.unwrap()))?;
-            let shader_fs = GlShader::from_source(&fs_src, gl::FRAGMENT_SHADER).map_err(|_| Error::RuntimeError("Failed to compile fragment shader!".to_string()) )?;
-            return Ok(Shader::from_shaders(vec![&shader_vs, &shader_gs, &shader_fs]));
-        } else if path_gs.is_none() && path_fs.is_none() {
-            //Default vertex shader
-            let vs_src = include_str!("../../shaders/default_vs.glsl");
-            let shader_vs = GlShader::from_source(&vs_src, gl::VERTEX_SHADER).map_err(|_| Error::RuntimeError("Failed to compile vertex shader!".to_string()) )?;
-            let fs_src = std::fs::read_to_string(wd.join(path_vs))?;
-            let shader_fs = GlShader::from_source(&fs_src, gl::FRAGMENT_SHADER).map_err(|_| Error::RuntimeError("Failed to compile fragment shader!".to_string()) )?;
-            return Ok(Shader::from_shaders(vec![&shader_vs, &shader_fs]));
+
+        //Vertex and fragment shaders get linked with the default for the other stage, to also catch mismatched inputs and outputs
+        let sources = match kind {
+            gl::VERTEX_SHADER => vec![source, ShaderSource::default_fragment()],
+            gl::FRAGMENT_SHADER => vec![ShaderSource::default_vertex(), source],
+            _ => vec![source],
+        };
+        let shaders = match compile_sources(&sources) {
+            Ok(shaders) => shaders,
+            Err(errors) => return Ok((false, Some(errors))),
+        };
+        if kind != gl::GEOMETRY_SHADER {
+            if let Err(log) = GlShaderProgram::from_shaders(shaders.iter().collect()) {
+                return Ok((false, Some(link_error(&sources, &log))));
+            }
         }
-        Err(Error::RuntimeError("Shader compilation failed! How did you trigger this?".to_string()))
+        Ok((true, None))
     });
 
     methods.add_method("setShader", |_, obj, shader: Option<Shader>| {
@@ -50,94 +79,449 @@ pub fn add_methods<'lua, M: UserDataMethods<'lua, crate::RendererGuard>>(methods
     });
 }
 
+/// The code of one shader stage, and the file it came from for error messages.
+struct ShaderSource {
+    kind: GLenum,
+    name: String,
+    code: String,
+}
+
+impl ShaderSource {
+    fn read(fs: &GameFs, path: &str, kind: GLenum) -> LuaResult<Self> {
+        let code = fs.read_to_string(path).map_err(|e| Error::RuntimeError(format!("Failed to read shader `{}`: {}", path, e)))?;
+        Ok(Self {
+            kind: kind,
+            name: path.to_string(),
+            code: code,
+        })
+    }
+
+    fn default_vertex() -> Self {
+        Self {
+            kind: gl::VERTEX_SHADER,
+            name: "default_vs.glsl".to_string(),
+            code: include_str!("../../shaders/default_vs.glsl").to_string(),
+        }
+    }
+
+    fn default_fragment() -> Self {
+        Self {
+            kind: gl::FRAGMENT_SHADER,
+            name: "default_fs.glsl".to_string(),
+            code: include_str!("../../shaders/default_fs.glsl").to_string(),
+        }
+    }
+
+    fn stage_name(&self) -> &'static str {
+        match self.kind {
+            gl::VERTEX_SHADER => "vertex",
+            gl::GEOMETRY_SHADER => "geometry",
+            gl::FRAGMENT_SHADER => "fragment",
+            _ => "compute",
+        }
+    }
+
+    /// Rewrites the compile log so every error points at the file and line it's about, and shows that line.
+    fn map_log(&self, log: &str) -> String {
+        let lines: Vec<&str> = self.code.lines().collect();
+        let mut mapped = Vec::new();
+        for log_line in log.lines().filter(|line| !line.trim().is_empty()) {
+            match parse_log_location(log_line) {
+                Some((line, message)) => {
+                    mapped.push(format!("{}:{}: {}", self.name, line, message));
+                    if let Some(code) = line.checked_sub(1).and_then(|i| lines.get(i)) {
+                        mapped.push(format!("    {} | {}", line, code.trim()));
+                    }
+                },
+                None => mapped.push(log_line.to_string()),
+            }
+        }
+        mapped.join("\n")
+    }
+}
+
+/// Finds the line number in a line of a compile log, and the message after it.
+/// Drivers don't agree on a format, this handles `0:12(5): error: ...` (Mesa),
+/// `0(12) : error C0000: ...` (Nvidia) and `ERROR: 0:12: ...` (AMD and Intel).
+fn parse_log_location(log_line: &str) -> Option<(usize, String)> {
+    let (severity, rest) = match log_line.find(": ") {
+        Some(i) if log_line[..i].chars().all(|c| c.is_ascii_uppercase()) => (&log_line[..i + 2], &log_line[i + 2..]),
+        _ => ("", log_line),
+    };
+
+    //The index of the source string, which is always 0 as every stage is a single string
+    let rest = rest.trim_start();
+    let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+    if digits == 0 {
+        return None;
+    }
+    let rest = &rest[digits..];
+
+    let (line, rest) = if let Some(rest) = rest.strip_prefix(':') {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let line = rest[..digits].parse().ok()?;
+        let mut rest = &rest[digits..];
+        //Mesa adds the column
+        if rest.starts_with('(') {
+            rest = &rest[rest.find(')')? + 1..];
+        }
+        (line, rest)
+    } else if let Some(rest) = rest.strip_prefix('(') {
+        let end = rest.find(')')?;
+        (rest[..end].parse().ok()?, &rest[end + 1..])
+    } else {
+        return None;
+    };
+
+    let message = rest.trim_start_matches(|c: char| c == ':' || c == ' ');
+    Some((line, format!("{}{}", severity.to_lowercase(), message)))
+}
+
+/// Compiles every stage, collecting the errors of all of them.
+fn compile_sources(sources: &[ShaderSource]) -> Result<Vec<GlShader>, String> {
+    let mut shaders = Vec::new();
+    let mut errors = Vec::new();
+    for source in sources {
+        match GlShader::from_source(&source.code, source.kind) {
+            Ok(shader) => shaders.push(shader),
+            Err(log) => errors.push(format!("Failed to compile {} shader `{}`:\n{}", source.stage_name(), source.name, source.map_log(&log))),
+        }
+    }
+    if errors.is_empty() {
+        Ok(shaders)
+    } else {
+        Err(errors.join("\n\n"))
+    }
+}
+
+fn link_error(sources: &[ShaderSource], log: &str) -> String {
+    let names: Vec<String> = sources.iter().map(|source| format!("`{}`", source.name)).collect();
+    format!("Failed to link {}:\n{}", names.join(", "), log)
+}
+
+lazy_static! {
+    static ref MAX_TEXTURE_UNITS: u32 = {
+        let mut value = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_TEXTURE_IMAGE_UNITS, &mut value) };
+        value as u32
+    };
+}
+
+/// The sampler the batch binds the texture being drawn to.
+const DRAWN_TEXTURE_SAMPLER: &str = "tex";
+
+/// Shaders used to multiply by this to get the draw color, it's kept at white so they don't turn black.
+const DEPRECATED_DRAW_COLOR: &str = "drawColor";
+
+#[derive(Clone, Copy, PartialEq)]
+enum UniformComponent {
+    Float,
+    Int,
+    UnsignedInt,
+    Bool,
+}
+
+/// What a uniform is made of, from the type GL reports for it.
+/// The count is the number of values in one element, so 9 for a `mat3`.
+fn uniform_layout(ty: GLenum) -> Option<(UniformComponent, usize)> {
+    use UniformComponent::*;
+    match ty {
+        gl::FLOAT => Some((Float, 1)),
+        gl::FLOAT_VEC2 => Some((Float, 2)),
+        gl::FLOAT_VEC3 => Some((Float, 3)),
+        gl::FLOAT_VEC4 => Some((Float, 4)),
+        gl::INT => Some((Int, 1)),
+        gl::INT_VEC2 => Some((Int, 2)),
+        gl::INT_VEC3 => Some((Int, 3)),
+        gl::INT_VEC4 => Some((Int, 4)),
+        gl::UNSIGNED_INT => Some((UnsignedInt, 1)),
+        gl::UNSIGNED_INT_VEC2 => Some((UnsignedInt, 2)),
+        gl::UNSIGNED_INT_VEC3 => Some((UnsignedInt, 3)),
+        gl::UNSIGNED_INT_VEC4 => Some((UnsignedInt, 4)),
+        gl::BOOL => Some((Bool, 1)),
+        gl::BOOL_VEC2 => Some((Bool, 2)),
+        gl::BOOL_VEC3 => Some((Bool, 3)),
+        gl::BOOL_VEC4 => Some((Bool, 4)),
+        gl::FLOAT_MAT2 => Some((Float, 4)),
+        gl::FLOAT_MAT3 => Some((Float, 9)),
+        gl::FLOAT_MAT4 => Some((Float, 16)),
+        _ => None,
+    }
+}
+
+fn is_matrix(ty: GLenum) -> bool {
+    ty == gl::FLOAT_MAT2 || ty == gl::FLOAT_MAT3 || ty == gl::FLOAT_MAT4
+}
+
+fn is_sampler(ty: GLenum) -> bool {
+    ty == gl::SAMPLER_2D || ty == gl::INT_SAMPLER_2D || ty == gl::UNSIGNED_INT_SAMPLER_2D
+}
+
+/// What the shader tells us about one of its uniforms.
+#[derive(Clone, Copy)]
+struct UniformInfo {
+    ty: GLenum,
+    ///The length of the array, 1 if it isn't one.
+    size: i32,
+    location: GLint,
+    ///The texture unit of a sampler, arrays of samplers take up the units after it too.
+    unit: Option<GLuint>,
+}
+
 #[derive(Clone)]
 pub struct Shader {
     pub raw_program: Arc<GlShaderProgram>,
-    uniform_hashmap: HashMap<String, GLenum>,
+    uniform_hashmap: HashMap<String, UniformInfo>,
+    ///Draws batched with the old uniform values have to be flushed before a uniform changes.
+    renderer: Option<Weak<Mutex<crate::Renderer>>>,
 }
 
 impl Shader {
-    pub fn from_shaders(shaders: Vec<&GlShader>) -> Self {
-        use std::ffi::CString;
-
-        let program = GlShaderProgram::from_shaders(shaders);
+    /// Links the shaders into a program, returning the link log if that fails.
+    pub fn from_shaders(shaders: Vec<&GlShader>) -> Result<Self, String> {
+        let program = GlShaderProgram::from_shaders(shaders)?;
         let mut map = HashMap::new();
         let mut count = 0;
-        unsafe { gl::GetProgramiv(program.id, gl::ACTIVE_UNIFORMS, &mut count); }
+        let mut max_length = 0;
+        unsafe {
+            gl::GetProgramiv(program.id, gl::ACTIVE_UNIFORMS, &mut count);
+            gl::GetProgramiv(program.id, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length);
+        }
+        //Unit 0 is for the texture being drawn, samplers the game sets get the ones after it
+        let mut next_unit = 1;
         for i in 0..count {
-            unsafe {
-                const BUFSIZE: i32 = 64;
-                let mut length = 0;
-                let mut size = 0;
-                let mut ty = 0;
-                let name: [i8; BUFSIZE as usize] = [0; BUFSIZE as usize];
-                gl::GetActiveUniform(program.id, i as u32, BUFSIZE, &mut length, &mut size, &mut ty, name.as_ptr() as *mut i8);
-                let name_u8: Vec<u8> = name.iter().map(|s| *s as u8).filter(|s| *s != 0).collect();
-                let name_str = CString::new(name_u8).expect("Failed to create cstring!").into_string().expect("Failed to create string!");
-                map.insert(name_str.trim().to_string(), ty);
+            let mut length = 0;
+            let mut size = 0;
+            let mut ty = 0;
+            let mut name = vec![0u8; max_length.max(1) as usize];
+            unsafe { gl::GetActiveUniform(program.id, i as u32, max_length, &mut length, &mut size, &mut ty, name.as_mut_ptr() as *mut GLchar); }
+            name.truncate(length as usize);
+            let name = String::from_utf8_lossy(&name).into_owned();
+            //Arrays are reported as `name[0]`, but set through their name
+            let name = name.trim_end_matches("[0]").to_string();
+
+            let cname = CString::new(name.clone()).expect("Failed to create cstring!");
+            let location = unsafe { gl::GetUniformLocation(program.id, cname.as_ptr()) };
+            if location < 0 {
+                //Uniforms in uniform blocks have no location, and can't be set one by one
+                continue;
+            }
+
+            if name == DEPRECATED_DRAW_COLOR && ty == gl::FLOAT_VEC4 {
+                unsafe { gl::ProgramUniform4f(program.id, location, 1.0, 1.0, 1.0, 1.0); }
             }
+
+            let unit = if !is_sampler(ty) {
+                None
+            } else if name == DRAWN_TEXTURE_SAMPLER {
+                Some(0)
+            } else {
+                let unit = next_unit;
+                next_unit += size as u32;
+                if next_unit > *MAX_TEXTURE_UNITS {
+                    warn!("Sampler `{}` doesn't fit in the {} texture units there are", name, *MAX_TEXTURE_UNITS);
+                }
+                let units: Vec<GLint> = (unit..unit + size as u32).map(|unit| unit as GLint).collect();
+                unsafe { gl::ProgramUniform1iv(program.id, location, size, units.as_ptr()); }
+                Some(unit)
+            };
+
+            map.insert(name, UniformInfo {
+                ty: ty,
+                size: size,
+                location: location,
+                unit: unit,
+            });
         }
-        Self {
+        Ok(Self {
             raw_program: Arc::new(program),
             uniform_hashmap: map,
-        }
+            renderer: None,
+        })
     }
 
-    fn get_uniform_type(&self, name: &str) -> LuaResult<&GLenum> {
-        self.uniform_hashmap.get(name).ok_or(LuaError::RuntimeError("Uniform does not exist!".to_string()))
+    fn with_renderer(mut self, renderer: &crate::RendererGuard) -> Self {
+        self.renderer = Some(renderer.downgrade());
+        self
     }
 
-    fn uniform_bool(&self, name: &str, value: bool) -> LuaResult<()> {
-        let ty = self.get_uniform_type(name)?;
-        match *ty {
-            gl::INT => Ok(self.raw_program.uniform(&name, value)),
-            gl::FLOAT => Ok(self.raw_program.uniform(&name, value as i32 as f32)),
-            _ => Err(LuaError::RuntimeError("Unknown uniform type!".to_string())),
+    fn flush_renderer(&self) {
+        if let Some(renderer) = self.renderer.as_ref().and_then(|renderer| renderer.upgrade()) {
+            renderer.lock().expect("Failed to acquire lock on renderer!").flush();
         }
     }
 
-    fn uniform_int(&self, name: &str, value: i32) -> LuaResult<()> {
-        let ty = self.get_uniform_type(name)?;
-        match *ty {
-            gl::INT => Ok(self.raw_program.uniform(&name, value)),
-            gl::FLOAT => Ok(self.raw_program.uniform(&name, value as f32)),
-            _ => Err(LuaError::RuntimeError("Unknown uniform type!".to_string())),
-        }
+    fn get_uniform_info(&self, name: &str) -> LuaResult<UniformInfo> {
+        self.uniform_hashmap.get(name).copied().ok_or_else(|| LuaError::RuntimeError(format!("Uniform `{}` does not exist, or isn't used by the shader!", name)))
     }
 
-    fn uniform_float(&self, name: &str, value: f32) -> LuaResult<()> {
-        let ty = self.get_uniform_type(name)?;
-        match *ty {
-            gl::INT => Ok(self.raw_program.uniform(&name, value as i32)),
-            gl::FLOAT => Ok(self.raw_program.uniform(&name, value)),
-            _ => Err(LuaError::RuntimeError("Unknown uniform type!".to_string())),
+    /// Sets a uniform from a number, boolean, table of them, or an image or canvas for samplers.
+    /// Arrays take a table with a value for each element, matrices a table of rows or all values row by row.
+    pub fn uniform(&self, name: &str, value: LuaValue) -> LuaResult<()> {
+        let info = self.get_uniform_info(name)?;
+        self.flush_renderer();
+
+        //Arrays take a table of elements, anything else is a single element
+        let elements = match value {
+            LuaValue::Table(table) if info.size > 1 => table.sequence_values::<LuaValue>().collect::<LuaResult<Vec<_>>>()?,
+            value => vec![value],
+        };
+        if elements.len() > info.size as usize {
+            return Err(LuaError::RuntimeError(format!("Uniform `{}` only has {} elements, got {}!", name, info.size, elements.len())));
+        }
+
+        if is_sampler(info.ty) {
+            return self.uniform_textures(name, &info, elements);
+        }
+
+        let (component, count) = uniform_layout(info.ty).ok_or_else(|| LuaError::RuntimeError(format!("Uniform `{}` is of a type that can't be set!", name)))?;
+        let mut values = Vec::with_capacity(elements.len() * count);
+        for element in elements {
+            let start = values.len();
+            match element {
+                LuaValue::Table(table) => {
+                    for value in table.sequence_values::<LuaValue>() {
+                        match value? {
+                            //Matrices can be given as a table of rows
+                            LuaValue::Table(row) if is_matrix(info.ty) => {
+                                for value in row.sequence_values::<LuaValue>() {
+                                    values.push(uniform_number(name, value?)?);
+                                }
+                            },
+                            value => values.push(uniform_number(name, value)?),
+                        }
+                    }
+                },
+                value => values.push(uniform_number(name, value)?),
+            }
+            if values.len() - start != count {
+                return Err(LuaError::RuntimeError(format!("Uniform `{}` expects {} values per element, got {}!", name, count, values.len() - start)));
+            }
         }
+
+        let id = self.raw_program.id;
+        let location = info.location;
+        let elements = (values.len() / count) as GLsizei;
+        unsafe {
+            match component {
+                UniformComponent::Float => {
+                    let values: Vec<f32> = values.iter().map(|v| *v as f32).collect();
+                    match (info.ty, count) {
+                        //Values are given row by row, GL expects them column by column
+                        (gl::FLOAT_MAT2, _) => gl::ProgramUniformMatrix2fv(id, location, elements, gl::TRUE, values.as_ptr()),
+                        (gl::FLOAT_MAT3, _) => gl::ProgramUniformMatrix3fv(id, location, elements, gl::TRUE, values.as_ptr()),
+                        (gl::FLOAT_MAT4, _) => gl::ProgramUniformMatrix4fv(id, location, elements, gl::TRUE, values.as_ptr()),
+                        (_, 1) => gl::ProgramUniform1fv(id, location, elements, values.as_ptr()),
+                        (_, 2) => gl::ProgramUniform2fv(id, location, elements, values.as_ptr()),
+                        (_, 3) => gl::ProgramUniform3fv(id, location, elements, values.as_ptr()),
+                        _ => gl::ProgramUniform4fv(id, location, elements, values.as_ptr()),
+                    }
+                },
+                UniformComponent::Int | UniformComponent::Bool => {
+                    let values: Vec<GLint> = values.iter().map(|v| *v as GLint).collect();
+                    match count {
+                        1 => gl::ProgramUniform1iv(id, location, elements, values.as_ptr()),
+                        2 => gl::ProgramUniform2iv(id, location, elements, values.as_ptr()),
+                        3 => gl::ProgramUniform3iv(id, location, elements, values.as_ptr()),
+                        _ => gl::ProgramUniform4iv(id, location, elements, values.as_ptr()),
+                    }
+                },
+                UniformComponent::UnsignedInt => {
+                    let values: Vec<GLuint> = values.iter().map(|v| *v as GLuint).collect();
+                    match count {
+                        1 => gl::ProgramUniform1uiv(id, location, elements, values.as_ptr()),
+                        2 => gl::ProgramUniform2uiv(id, location, elements, values.as_ptr()),
+                        3 => gl::ProgramUniform3uiv(id, location, elements, values.as_ptr()),
+                        _ => gl::ProgramUniform4uiv(id, location, elements, values.as_ptr()),
+                    }
+                },
+            }
+        }
+        Ok(())
     }
 
-    pub fn uniform(&self, name: String, value: LuaValue) -> LuaResult<()> {
-        match value {
-            LuaValue::Boolean(v) => self.uniform_bool(&name, v),
-            LuaValue::Integer(v) => self.uniform_int(&name, v as i32),
-            LuaValue::Number(v) => self.uniform_float(&name, v as f32),
-            LuaValue::Table(v) => todo!(),
-            LuaValue::UserData(v) => todo!(),
-            _ => panic!("Unsupported uniform type!")
+    fn uniform_textures(&self, name: &str, info: &UniformInfo, elements: Vec<LuaValue>) -> LuaResult<()> {
+        let unit = match info.unit {
+            Some(0) => return Err(LuaError::RuntimeError(format!("`{}` is the texture being drawn, it can't be set!", name))),
+            Some(unit) => unit,
+            None => return Err(LuaError::RuntimeError(format!("Uniform `{}` is not a sampler!", name))),
+        };
+        for (i, element) in elements.into_iter().enumerate() {
+            let texture = match element {
+                LuaValue::UserData(data) if data.is::<Image>() => data.borrow::<Image>()?.texture.clone(),
+                LuaValue::UserData(data) if data.is::<Canvas>() => data.borrow::<Canvas>()?.image.texture.clone(),
+                _ => return Err(LuaError::RuntimeError(format!("Sampler `{}` expects an image or canvas!", name))),
+            };
+            self.raw_program.set_texture(unit + i as GLuint, texture);
         }
+        Ok(())
+    }
+}
+
+/// Numbers and booleans, as booleans are set as 0 or 1.
+fn uniform_number(name: &str, value: LuaValue) -> LuaResult<f64> {
+    match value {
+        LuaValue::Integer(v) => Ok(v as f64),
+        LuaValue::Number(v) => Ok(v),
+        LuaValue::Boolean(v) => Ok(v as i32 as f64),
+        _ => Err(LuaError::RuntimeError(format!("Uniform `{}` can only be set to numbers and booleans!", name))),
     }
 }
 
 impl UserData for Shader {
     fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
         methods.add_method("uniform", |_, obj, (name, value): (String, LuaValue)| {
-            obj.uniform(name, value)?;
+            obj.uniform(&name, value)?;
             Ok(())
         });
     }
 }
 
-#[derive(Clone)]
-pub struct Uniform {
-    pub raw: Arc<Box<dyn UniformValue>>,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mesa_logs() {
+        assert_eq!(parse_log_location("0:12(5): error: `foo' undeclared"), Some((12, "error: `foo' undeclared".to_string())));
+    }
 
-impl UserData for Uniform {}
+    #[test]
+    fn parses_nvidia_logs() {
+        assert_eq!(parse_log_location("0(12) : error C1008: undefined variable \"foo\""), Some((12, "error C1008: undefined variable \"foo\"".to_string())));
+    }
+
+    #[test]
+    fn parses_amd_logs() {
+        assert_eq!(parse_log_location("ERROR: 0:12: 'foo' : undeclared identifier"), Some((12, "error: 'foo' : undeclared identifier".to_string())));
+        assert_eq!(parse_log_location("WARNING: 0:3: extension not supported"), Some((3, "warning: extension not supported".to_string())));
+    }
+
+    #[test]
+    fn leaves_other_lines_alone() {
+        assert_eq!(parse_log_location("ERROR: 1 compilation errors.  No code generated."), None);
+        assert_eq!(parse_log_location("error: linking failed"), None);
+        assert_eq!(parse_log_location(""), None);
+    }
+
+    #[test]
+    fn maps_logs_to_the_file_and_line() {
+        let source = ShaderSource {
+            kind: gl::FRAGMENT_SHADER,
+            name: "test_fs.glsl".to_string(),
+            code: "#version 450 core\nvoid main() {\n    foo = 1.0;\n}\n".to_string(),
+        };
+        let log = "0:3(5): error: `foo' undeclared\nsomething else\n";
+        assert_eq!(source.map_log(log), "test_fs.glsl:3: error: `foo' undeclared\n    3 | foo = 1.0;\nsomething else");
+    }
+
+    #[test]
+    fn lines_past_the_end_are_not_shown() {
+        let source = ShaderSource {
+            kind: gl::VERTEX_SHADER,
+            name: "test_vs.glsl".to_string(),
+            code: "void main() {}".to_string(),
+        };
+        assert_eq!(source.map_log("0:9(1): error: unexpected end of file"), "test_vs.glsl:9: error: unexpected end of file");
+    }
+}
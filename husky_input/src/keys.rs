@@ -0,0 +1,112 @@
+use glutin::event::{VirtualKeyCode, MouseButton};
+
+/// Names used for keys on the Lua side. These follow Love2D's `KeyConstant`
+/// names wherever glutin has a matching key, so existing Love2D code mostly
+/// carries over.
+static KEY_NAMES: &[(VirtualKeyCode, &str)] = &[
+    (VirtualKeyCode::A, "a"), (VirtualKeyCode::B, "b"), (VirtualKeyCode::C, "c"),
+    (VirtualKeyCode::D, "d"), (VirtualKeyCode::E, "e"), (VirtualKeyCode::F, "f"),
+    (VirtualKeyCode::G, "g"), (VirtualKeyCode::H, "h"), (VirtualKeyCode::I, "i"),
+    (VirtualKeyCode::J, "j"), (VirtualKeyCode::K, "k"), (VirtualKeyCode::L, "l"),
+    (VirtualKeyCode::M, "m"), (VirtualKeyCode::N, "n"), (VirtualKeyCode::O, "o"),
+    (VirtualKeyCode::P, "p"), (VirtualKeyCode::Q, "q"), (VirtualKeyCode::R, "r"),
+    (VirtualKeyCode::S, "s"), (VirtualKeyCode::T, "t"), (VirtualKeyCode::U, "u"),
+    (VirtualKeyCode::V, "v"), (VirtualKeyCode::W, "w"), (VirtualKeyCode::X, "x"),
+    (VirtualKeyCode::Y, "y"), (VirtualKeyCode::Z, "z"),
+
+    (VirtualKeyCode::Key0, "0"), (VirtualKeyCode::Key1, "1"), (VirtualKeyCode::Key2, "2"),
+    (VirtualKeyCode::Key3, "3"), (VirtualKeyCode::Key4, "4"), (VirtualKeyCode::Key5, "5"),
+    (VirtualKeyCode::Key6, "6"), (VirtualKeyCode::Key7, "7"), (VirtualKeyCode::Key8, "8"),
+    (VirtualKeyCode::Key9, "9"),
+
+    (VirtualKeyCode::Space, "space"),
+    (VirtualKeyCode::Return, "return"),
+    (VirtualKeyCode::Escape, "escape"),
+    (VirtualKeyCode::Back, "backspace"),
+    (VirtualKeyCode::Tab, "tab"),
+
+    (VirtualKeyCode::Up, "up"), (VirtualKeyCode::Down, "down"),
+    (VirtualKeyCode::Left, "left"), (VirtualKeyCode::Right, "right"),
+
+    (VirtualKeyCode::LShift, "lshift"), (VirtualKeyCode::RShift, "rshift"),
+    (VirtualKeyCode::LControl, "lctrl"), (VirtualKeyCode::RControl, "rctrl"),
+    (VirtualKeyCode::LAlt, "lalt"), (VirtualKeyCode::RAlt, "ralt"),
+    (VirtualKeyCode::LWin, "lgui"), (VirtualKeyCode::RWin, "rgui"),
+
+    (VirtualKeyCode::Insert, "insert"), (VirtualKeyCode::Delete, "delete"),
+    (VirtualKeyCode::Home, "home"), (VirtualKeyCode::End, "end"),
+    (VirtualKeyCode::PageUp, "pageup"), (VirtualKeyCode::PageDown, "pagedown"),
+
+    (VirtualKeyCode::Snapshot, "printscreen"),
+    (VirtualKeyCode::Scroll, "scrolllock"),
+    (VirtualKeyCode::Pause, "pause"),
+    (VirtualKeyCode::Numlock, "numlock"),
+    (VirtualKeyCode::Capital, "capslock"),
+    (VirtualKeyCode::Apps, "application"),
+
+    (VirtualKeyCode::F1, "f1"), (VirtualKeyCode::F2, "f2"), (VirtualKeyCode::F3, "f3"),
+    (VirtualKeyCode::F4, "f4"), (VirtualKeyCode::F5, "f5"), (VirtualKeyCode::F6, "f6"),
+    (VirtualKeyCode::F7, "f7"), (VirtualKeyCode::F8, "f8"), (VirtualKeyCode::F9, "f9"),
+    (VirtualKeyCode::F10, "f10"), (VirtualKeyCode::F11, "f11"), (VirtualKeyCode::F12, "f12"),
+    (VirtualKeyCode::F13, "f13"), (VirtualKeyCode::F14, "f14"), (VirtualKeyCode::F15, "f15"),
+    (VirtualKeyCode::F16, "f16"), (VirtualKeyCode::F17, "f17"), (VirtualKeyCode::F18, "f18"),
+    (VirtualKeyCode::F19, "f19"), (VirtualKeyCode::F20, "f20"), (VirtualKeyCode::F21, "f21"),
+    (VirtualKeyCode::F22, "f22"), (VirtualKeyCode::F23, "f23"), (VirtualKeyCode::F24, "f24"),
+
+    (VirtualKeyCode::Numpad0, "kp0"), (VirtualKeyCode::Numpad1, "kp1"), (VirtualKeyCode::Numpad2, "kp2"),
+    (VirtualKeyCode::Numpad3, "kp3"), (VirtualKeyCode::Numpad4, "kp4"), (VirtualKeyCode::Numpad5, "kp5"),
+    (VirtualKeyCode::Numpad6, "kp6"), (VirtualKeyCode::Numpad7, "kp7"), (VirtualKeyCode::Numpad8, "kp8"),
+    (VirtualKeyCode::Numpad9, "kp9"),
+    (VirtualKeyCode::NumpadAdd, "kp+"),
+    (VirtualKeyCode::NumpadSubtract, "kp-"),
+    (VirtualKeyCode::NumpadMultiply, "kp*"),
+    (VirtualKeyCode::NumpadDivide, "kp/"),
+    (VirtualKeyCode::NumpadDecimal, "kp."),
+    (VirtualKeyCode::NumpadComma, "kp,"),
+    (VirtualKeyCode::NumpadEquals, "kp="),
+    (VirtualKeyCode::NumpadEnter, "kpenter"),
+
+    (VirtualKeyCode::Apostrophe, "'"),
+    (VirtualKeyCode::Comma, ","),
+    (VirtualKeyCode::Minus, "-"),
+    (VirtualKeyCode::Period, "."),
+    (VirtualKeyCode::Slash, "/"),
+    (VirtualKeyCode::Semicolon, ";"),
+    (VirtualKeyCode::Equals, "="),
+    (VirtualKeyCode::LBracket, "["),
+    (VirtualKeyCode::Backslash, "\\"),
+    (VirtualKeyCode::RBracket, "]"),
+    (VirtualKeyCode::Grave, "`"),
+    (VirtualKeyCode::Asterisk, "*"),
+    (VirtualKeyCode::Plus, "+"),
+    (VirtualKeyCode::At, "@"),
+    (VirtualKeyCode::Colon, ":"),
+    (VirtualKeyCode::Caret, "^"),
+];
+
+/// Returns the Lua name for a key, or `"unknown"` if we don't have one.
+pub fn key_to_str(key: VirtualKeyCode) -> &'static str {
+    KEY_NAMES.iter()
+        .find(|(code, _)| *code == key)
+        .map(|(_, name)| *name)
+        .unwrap_or("unknown")
+}
+
+/// Looks up a key by its Lua name.
+pub fn str_to_key(name: &str) -> Option<VirtualKeyCode> {
+    KEY_NAMES.iter()
+        .find(|(_, key_name)| *key_name == name)
+        .map(|(code, _)| *code)
+}
+
+/// Mouse buttons are numbered like in Love2D: 1 is the left button,
+/// 2 the right button and 3 the middle button. Any other buttons are passed
+/// through with the index the platform gave them.
+pub fn button_to_index(button: MouseButton) -> u16 {
+    match button {
+        MouseButton::Left => 1,
+        MouseButton::Right => 2,
+        MouseButton::Middle => 3,
+        MouseButton::Other(idx) => idx,
+    }
+}
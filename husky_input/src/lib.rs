@@ -0,0 +1,101 @@
+#[macro_use] extern crate lazy_static;
+
+use std::sync::Mutex;
+
+use glutin::event::VirtualKeyCode;
+
+use mlua::prelude::*;
+use mlua::{UserData, UserDataMethods, Variadic};
+
+pub mod keys;
+mod state;
+
+pub use state::InputState;
+
+lazy_static! {
+    pub static ref INPUT_STATE: Mutex<InputState> = Mutex::new(InputState::new());
+}
+
+fn lua_key(name: &str) -> LuaResult<VirtualKeyCode> {
+    keys::str_to_key(name).ok_or_else(|| LuaError::RuntimeError(format!("Invalid key constant: {}", name)))
+}
+
+/// `husky.keyboard`. Unlike `husky.graphics`, these are plain functions,
+/// so they are called with a dot: `husky.keyboard.isDown("w")`.
+#[derive(Clone)]
+pub struct KeyboardInterface;
+
+impl KeyboardInterface {
+    pub fn new() -> Self {
+        Self {
+
+        }
+    }
+}
+
+impl UserData for KeyboardInterface {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        //Returns true if any of the passed keys is held down
+        methods.add_function("isDown", |_, keys: Variadic<String>| {
+            let state = INPUT_STATE.lock().unwrap();
+            for key in keys.iter() {
+                if state.is_key_down(lua_key(key)?) { return Ok(true); }
+            }
+            Ok(false)
+        });
+
+        methods.add_function("wasPressed", |_, key: String| {
+            Ok(INPUT_STATE.lock().unwrap().was_key_pressed(lua_key(&key)?))
+        });
+
+        methods.add_function("wasReleased", |_, key: String| {
+            Ok(INPUT_STATE.lock().unwrap().was_key_released(lua_key(&key)?))
+        });
+
+        methods.add_function("isValidKey", |_, key: String| {
+            Ok(keys::str_to_key(&key).is_some())
+        });
+    }
+}
+
+/// `husky.mouse`, called with a dot just like `husky.keyboard`.
+#[derive(Clone)]
+pub struct MouseInterface;
+
+impl MouseInterface {
+    pub fn new() -> Self {
+        Self {
+
+        }
+    }
+}
+
+impl UserData for MouseInterface {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_function("getPosition", |_, ()| {
+            Ok(INPUT_STATE.lock().unwrap().mouse_pos())
+        });
+
+        methods.add_function("getX", |_, ()| {
+            Ok(INPUT_STATE.lock().unwrap().mouse_pos().0)
+        });
+
+        methods.add_function("getY", |_, ()| {
+            Ok(INPUT_STATE.lock().unwrap().mouse_pos().1)
+        });
+
+        //Returns true if any of the passed buttons is held down
+        methods.add_function("isDown", |_, buttons: Variadic<u16>| {
+            let state = INPUT_STATE.lock().unwrap();
+            Ok(buttons.iter().any(|button| state.is_button_down(*button)))
+        });
+
+        methods.add_function("wasPressed", |_, button: u16| {
+            Ok(INPUT_STATE.lock().unwrap().was_button_pressed(button))
+        });
+
+        methods.add_function("wasReleased", |_, button: u16| {
+            Ok(INPUT_STATE.lock().unwrap().was_button_released(button))
+        });
+    }
+}
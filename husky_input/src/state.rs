@@ -0,0 +1,185 @@
+use std::collections::{HashMap, HashSet};
+
+use glutin::event::VirtualKeyCode;
+
+/// Input state as seen by the game for the current frame.
+/// The window loop feeds events into this, and calls `end_frame`
+/// once the frame has been handled, so the pressed/released sets
+/// only ever hold what happened since the previous frame.
+#[derive(Default)]
+pub struct InputState {
+    ///Held keys, with the scancode they were pressed with.
+    keys_down: HashMap<VirtualKeyCode, u32>,
+    keys_pressed: HashSet<VirtualKeyCode>,
+    keys_released: HashSet<VirtualKeyCode>,
+
+    buttons_down: HashSet<u16>,
+    buttons_pressed: HashSet<u16>,
+    buttons_released: HashSet<u16>,
+
+    mouse_pos: (f32, f32),
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if the key was already held down, which means this is a key repeat.
+    pub fn press_key(&mut self, key: VirtualKeyCode, scancode: u32) -> bool {
+        let is_repeat = self.keys_down.insert(key, scancode).is_some();
+        if !is_repeat {
+            self.keys_pressed.insert(key);
+        }
+        is_repeat
+    }
+
+    pub fn release_key(&mut self, key: VirtualKeyCode) {
+        if self.keys_down.remove(&key).is_some() {
+            self.keys_released.insert(key);
+        }
+    }
+
+    pub fn press_button(&mut self, button: u16) {
+        if self.buttons_down.insert(button) {
+            self.buttons_pressed.insert(button);
+        }
+    }
+
+    pub fn release_button(&mut self, button: u16) {
+        if self.buttons_down.remove(&button) {
+            self.buttons_released.insert(button);
+        }
+    }
+
+    /// Returns how far the mouse moved since the last position we knew of.
+    pub fn move_mouse(&mut self, pos: (f32, f32)) -> (f32, f32) {
+        let delta = (pos.0 - self.mouse_pos.0, pos.1 - self.mouse_pos.1);
+        self.mouse_pos = pos;
+        delta
+    }
+
+    /// Releases everything that is still held down, for when the window loses focus
+    /// and we won't get the release events anymore.
+    /// Returns the keys with their scancodes, and the buttons, that were released.
+    pub fn release_all(&mut self) -> (Vec<(VirtualKeyCode, u32)>, Vec<u16>) {
+        let keys: Vec<(VirtualKeyCode, u32)> = self.keys_down.drain().collect();
+        let buttons: Vec<u16> = self.buttons_down.drain().collect();
+        self.keys_released.extend(keys.iter().map(|&(key, _)| key));
+        self.buttons_released.extend(buttons.iter().copied());
+        (keys, buttons)
+    }
+
+    pub fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+    }
+
+    pub fn is_key_down(&self, key: VirtualKeyCode) -> bool {
+        self.keys_down.contains_key(&key)
+    }
+
+    pub fn was_key_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    pub fn was_key_released(&self, key: VirtualKeyCode) -> bool {
+        self.keys_released.contains(&key)
+    }
+
+    pub fn is_button_down(&self, button: u16) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    pub fn was_button_pressed(&self, button: u16) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    pub fn was_button_released(&self, button: u16) -> bool {
+        self.buttons_released.contains(&button)
+    }
+
+    pub fn mouse_pos(&self) -> (f32, f32) {
+        self.mouse_pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_and_release_last_one_frame() {
+        let mut state = InputState::new();
+        assert!(!state.press_key(VirtualKeyCode::A, 30));
+        assert!(state.is_key_down(VirtualKeyCode::A));
+        assert!(state.was_key_pressed(VirtualKeyCode::A));
+
+        state.end_frame();
+        assert!(state.is_key_down(VirtualKeyCode::A));
+        assert!(!state.was_key_pressed(VirtualKeyCode::A));
+
+        state.release_key(VirtualKeyCode::A);
+        assert!(!state.is_key_down(VirtualKeyCode::A));
+        assert!(state.was_key_released(VirtualKeyCode::A));
+
+        state.end_frame();
+        assert!(!state.was_key_released(VirtualKeyCode::A));
+    }
+
+    #[test]
+    fn key_repeats_are_not_new_presses() {
+        let mut state = InputState::new();
+        state.press_key(VirtualKeyCode::A, 30);
+        state.end_frame();
+        assert!(state.press_key(VirtualKeyCode::A, 30));
+        assert!(!state.was_key_pressed(VirtualKeyCode::A));
+    }
+
+    #[test]
+    fn releasing_what_isnt_held_does_nothing() {
+        let mut state = InputState::new();
+        state.release_key(VirtualKeyCode::A);
+        state.release_button(1);
+        assert!(!state.was_key_released(VirtualKeyCode::A));
+        assert!(!state.was_button_released(1));
+    }
+
+    #[test]
+    fn pressed_and_released_in_the_same_frame() {
+        let mut state = InputState::new();
+        state.press_button(1);
+        state.release_button(1);
+        assert!(!state.is_button_down(1));
+        assert!(state.was_button_pressed(1));
+        assert!(state.was_button_released(1));
+    }
+
+    #[test]
+    fn release_all_returns_what_was_held() {
+        let mut state = InputState::new();
+        state.press_key(VirtualKeyCode::A, 30);
+        state.press_button(2);
+        state.end_frame();
+
+        let (keys, buttons) = state.release_all();
+        assert_eq!(keys, vec![(VirtualKeyCode::A, 30)]);
+        assert_eq!(buttons, vec![2]);
+        assert!(!state.is_key_down(VirtualKeyCode::A));
+        assert!(state.was_key_released(VirtualKeyCode::A));
+        assert!(state.was_button_released(2));
+
+        let (keys, buttons) = state.release_all();
+        assert!(keys.is_empty() && buttons.is_empty());
+    }
+
+    #[test]
+    fn mouse_moves_report_the_delta() {
+        let mut state = InputState::new();
+        assert_eq!(state.move_mouse((10.0, 5.0)), (10.0, 5.0));
+        assert_eq!(state.move_mouse((4.0, 8.0)), (-6.0, 3.0));
+        assert_eq!(state.mouse_pos(), (4.0, 8.0));
+    }
+}
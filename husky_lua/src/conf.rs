@@ -0,0 +1,178 @@
+use mlua::{Table, Function, LuaOptions, StdLib};
+use mlua::prelude::*;
+
+use husky_fs::GameFs;
+
+/// Which parts of the `husky` table get loaded.
+#[derive(Clone, Debug)]
+pub struct Modules {
+    pub graphics: bool,
+    pub voxel: bool,
+    pub keyboard: bool,
+    pub mouse: bool,
+    pub timer: bool,
+}
+
+impl Default for Modules {
+    fn default() -> Self {
+        Self {
+            graphics: true,
+            voxel: true,
+            keyboard: true,
+            mouse: true,
+            timer: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WindowConfig {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub resizable: bool,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// 0 disables multisampling.
+    pub msaa: u16,
+    pub min_width: Option<u32>,
+    pub min_height: Option<u32>,
+    /// Draws the FPS in the corner of the window.
+    pub show_fps: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "Husky v0.0.1".to_string(),
+            width: 1280,
+            height: 720,
+            resizable: true,
+            fullscreen: false,
+            vsync: false,
+            msaa: 0,
+            min_width: None,
+            min_height: None,
+            show_fps: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TimestepConfig {
+    /// Steps per second for `husky.fixedupdate`. `None` turns fixed steps off.
+    pub fixed_rate: Option<f32>,
+    /// Most fixed steps to run in one frame. When a frame takes longer than this
+    /// many steps, the rest of the time is dropped instead of trying to catch up.
+    pub max_steps: u32,
+}
+
+impl Default for TimestepConfig {
+    fn default() -> Self {
+        Self {
+            fixed_rate: None,
+            max_steps: 5,
+        }
+    }
+}
+
+/// Settings a game can change through `husky.conf(t)` in `conf.lua`.
+/// These have to be known before the window is created, which is why they
+/// can't be set from `main.lua`.
+#[derive(Clone, Debug, Default)]
+pub struct GameConfig {
+    pub window: WindowConfig,
+    pub timestep: TimestepConfig,
+    pub modules: Modules,
+}
+
+impl GameConfig {
+    /// Runs `conf.lua` if the game has one. Without it, the defaults are used.
+    pub fn load(fs: &GameFs) -> LuaResult<Self> {
+        let mut config = Self::default();
+        if !fs.is_file("conf.lua") {
+            return Ok(config);
+        }
+
+        let source = fs.read("conf.lua").map_err(|e| LuaError::RuntimeError(format!("Failed to read conf.lua: {}", e)))?;
+
+        //conf.lua runs before anything else exists, so it only gets the pure libraries
+        let lua = Lua::new_with(StdLib::TABLE | StdLib::STRING | StdLib::MATH, LuaOptions::new())?;
+        lua.globals().set("husky", lua.create_table()?)?;
+        lua.load(&source).set_name("@conf.lua")?.exec()?;
+
+        let api: Table = lua.globals().get("husky")?;
+        if let Some(conf) = api.get::<_, Option<Function>>("conf")? {
+            let t = config.to_table(&lua)?;
+            conf.call::<_, ()>(t.clone())?;
+            config.read_table(&t)?;
+        }
+
+        Ok(config)
+    }
+
+    fn to_table<'lua>(&self, lua: &'lua Lua) -> LuaResult<Table<'lua>> {
+        let window = lua.create_table()?;
+        window.set("title", self.window.title.as_str())?;
+        window.set("width", self.window.width)?;
+        window.set("height", self.window.height)?;
+        window.set("resizable", self.window.resizable)?;
+        window.set("fullscreen", self.window.fullscreen)?;
+        window.set("vsync", self.window.vsync)?;
+        window.set("msaa", self.window.msaa)?;
+        window.set("minwidth", self.window.min_width)?;
+        window.set("minheight", self.window.min_height)?;
+        window.set("showfps", self.window.show_fps)?;
+
+        let timestep = lua.create_table()?;
+        timestep.set("fixedrate", self.timestep.fixed_rate)?;
+        timestep.set("maxsteps", self.timestep.max_steps)?;
+
+        let modules = lua.create_table()?;
+        modules.set("graphics", self.modules.graphics)?;
+        modules.set("voxel", self.modules.voxel)?;
+        modules.set("keyboard", self.modules.keyboard)?;
+        modules.set("mouse", self.modules.mouse)?;
+        modules.set("timer", self.modules.timer)?;
+
+        let t = lua.create_table()?;
+        t.set("window", window)?;
+        t.set("timestep", timestep)?;
+        t.set("modules", modules)?;
+        Ok(t)
+    }
+
+    fn read_table(&mut self, t: &Table) -> LuaResult<()> {
+        let window: Table = t.get("window")?;
+        self.window.title = window.get("title")?;
+        self.window.width = window.get("width")?;
+        self.window.height = window.get("height")?;
+        self.window.resizable = window.get("resizable")?;
+        self.window.fullscreen = window.get("fullscreen")?;
+        self.window.vsync = window.get("vsync")?;
+        self.window.msaa = window.get("msaa")?;
+        self.window.min_width = window.get("minwidth")?;
+        self.window.min_height = window.get("minheight")?;
+        self.window.show_fps = window.get("showfps")?;
+
+        let timestep: Table = t.get("timestep")?;
+        self.timestep.fixed_rate = timestep.get("fixedrate")?;
+        self.timestep.max_steps = timestep.get("maxsteps")?;
+        if self.timestep.fixed_rate.is_some_and(|rate| rate <= 0.0) {
+            return Err(LuaError::RuntimeError("t.timestep.fixedrate must be above 0".to_string()));
+        }
+        //Without a single step per frame, husky.fixedupdate would never run
+        if self.timestep.max_steps < 1 {
+            return Err(LuaError::RuntimeError("t.timestep.maxsteps must be at least 1".to_string()));
+        }
+
+        let modules: Table = t.get("modules")?;
+        self.modules.graphics = modules.get("graphics")?;
+        self.modules.voxel = modules.get("voxel")?;
+        self.modules.keyboard = modules.get("keyboard")?;
+        self.modules.mouse = modules.get("mouse")?;
+        self.modules.timer = modules.get("timer")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use mlua::prelude::*;
+
+use husky_graphics::RendererGuard;
+
+const LINE_HEIGHT: f32 = 26.0;
+const MARGIN: f32 = 20.0;
+
+/// Turns a Lua error into something readable, including the traceback.
+pub fn format_error(err: &LuaError) -> String {
+    match err {
+        //The cause of a callback error is the interesting part,
+        //the default formatting only gives us the traceback.
+        LuaError::CallbackError { traceback, cause } => format!("{}\n{}", format_error(cause), traceback),
+        _ => err.to_string(),
+    }
+}
+
+/// Draws a full-screen error message, in place of the program's own `husky.draw`.
+pub fn draw(renderer: &RendererGuard, message: &str) {
+    let mut renderer = renderer.get_lock();
+    renderer.begin_frame();
+    //Whatever scissor, color mask or stencil test the game left set would clip the background
+    let render_state = std::mem::take(&mut renderer.renderer2d.render_state);
+    renderer.renderer2d.apply_render_state();
+    renderer.clear(0.35, 0.1, 0.15, 1.0);
+
+    let color = (1.0, 1.0, 1.0, 1.0);
+
+    let lines = std::iter::once("Error")
+        .chain(std::iter::once(""))
+        .chain(message.lines())
+        .chain(std::iter::once(""))
+        .chain(std::iter::once("Press R to reload, or escape to quit."));
+    for (i, line) in lines.enumerate() {
+        let y = MARGIN + i as f32 * LINE_HEIGHT;
+        renderer.print_overlay(color, &line.replace('\t', "    "), MARGIN, y);
+    }
+
+    renderer.flush();
+    renderer.renderer2d.render_state = render_state;
+    renderer.renderer2d.apply_render_state();
+    renderer.finish_frame();
+}
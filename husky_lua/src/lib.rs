@@ -1,16 +1,57 @@
 #[macro_use] extern crate log;
 
-use std::ops::{Deref, DerefMut};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use mlua::{Chunk, Table, Function};
+use mlua::{Table, Function, ToLuaMulti};
 use mlua::prelude::*;
 
 use husky_graphics::RendererGuard;
 use husky_voxel::VoxelInterface;
+use husky_input::{KeyboardInterface, MouseInterface};
+use husky_fs::GameFs;
+
+mod error_screen;
+mod saved_state;
+mod require;
+mod conf;
+mod timer;
+
+pub use saved_state::SavedState;
+pub use conf::{GameConfig, WindowConfig, TimestepConfig, Modules};
+use timer::Timer;
+
+/// How a program should be set up, on top of its source.
+#[derive(Clone, Default)]
+pub struct ProgramOptions {
+    /// Exposed to Lua as `husky.args`.
+    pub args: Vec<String>,
+    /// Without a window there's no GL context, so `husky.graphics` is left out.
+    pub headless: bool,
+    /// Set from `conf.lua`.
+    pub modules: Modules,
+    /// Draws the FPS in the corner, on top of everything the program draws.
+    pub show_fps: bool,
+}
 
 pub struct LuaProgram {
     lua: Lua,
-    working_directory: String,
+    ///`None` when running headless, or when `conf.lua` turned graphics off.
+    renderer: Option<RendererGuard>,
+
+    ///Set from Lua through `husky.quit()`.
+    quit_requested: Arc<AtomicBool>,
+
+    ///Shared with `husky.timer`.
+    timer: Rc<RefCell<Timer>>,
+    show_fps: bool,
+
+    ///Once the program hits an error, it stops running and only shows the error screen.
+    error: RefCell<Option<String>>,
+    ///Set when the user asks to reload from the error screen.
+    reload_requested: Cell<bool>,
 }
 
 impl LuaProgram {
@@ -18,21 +59,104 @@ impl LuaProgram {
         Lua::new()
     }
 
-    pub fn from_source(working_directory: String, source: &str) -> LuaResult<Self> {
+    /// Only fails if the Lua environment itself can't be set up.
+    /// Errors in the program's source show up on the error screen instead.
+    pub fn from_source(fs: GameFs, source: &str, options: &ProgramOptions) -> LuaResult<Self> {
         let lua = Self::new_lua_env();
+        require::install_searcher(&lua, fs.clone())?;
+
         let api_table = lua.create_table()?;
+        api_table.set("args", options.args.clone())?;
 
-        api_table.set("graphics", RendererGuard::new(working_directory.clone()))?;
-        api_table.set("voxel", VoxelInterface::new())?;
+        let modules = &options.modules;
+        let renderer = if options.headless || !modules.graphics { None } else { Some(RendererGuard::new(fs)) };
+        if let Some(renderer) = &renderer {
+            api_table.set("graphics", renderer.clone())?;
+        }
+        if modules.voxel { api_table.set("voxel", VoxelInterface::new())?; }
+        if modules.keyboard { api_table.set("keyboard", KeyboardInterface::new())?; }
+        if modules.mouse { api_table.set("mouse", MouseInterface::new())?; }
 
-        lua.globals().set("husky", api_table)?;
+        let timer = Rc::new(RefCell::new(Timer::new()));
+        if modules.timer { api_table.set("timer", timer::create_interface(&lua, timer.clone())?)?; }
+
+        let quit_requested = Arc::new(AtomicBool::new(false));
+        let quit_flag = quit_requested.clone();
+        api_table.set("quit", lua.create_function(move |_, ()| {
+            quit_flag.store(true, Ordering::Relaxed);
+            Ok(())
+        })?)?;
 
-        lua.load(source).exec()?;
+        lua.globals().set("husky", api_table)?;
 
-        Ok(Self {
+        let program = Self {
             lua: lua,
-            working_directory: working_directory
-        })
+            renderer: renderer,
+
+            quit_requested: quit_requested,
+
+            timer: timer,
+            show_fps: options.show_fps,
+
+            error: RefCell::new(None),
+            reload_requested: Cell::new(false),
+        };
+
+        if let Err(e) = program.lua.load(source).set_name("@main.lua")?.exec() {
+            program.report_error(&e);
+        }
+
+        Ok(program)
+    }
+
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested.load(Ordering::Relaxed)
+    }
+
+    pub fn reload_requested(&self) -> bool {
+        self.reload_requested.get()
+    }
+
+    pub fn has_error(&self) -> bool {
+        self.error.borrow().is_some()
+    }
+
+    fn report_error(&self, err: &LuaError) {
+        let message = error_screen::format_error(err);
+        error!("{}", message);
+        *self.error.borrow_mut() = Some(message);
+    }
+
+    fn get_callback<'lua>(&'lua self, name: &str) -> LuaResult<Option<Function<'lua>>> {
+        match self.lua.globals().get::<_, Option<Table>>("husky")? {
+            Some(api) => api.get(name),
+            None => Ok(None),
+        }
+    }
+
+    /// Calls `husky.<name>` if the program defined it.
+    /// Returns whether the callback existed.
+    fn call_callback<'lua, A: ToLuaMulti<'lua>>(&'lua self, name: &str, args: A) -> LuaResult<bool> {
+        match self.get_callback(name)? {
+            Some(callback) => {
+                callback.call::<_, ()>(args)?;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// Same as `call_callback`, but errors are sent to the error screen.
+    /// Does nothing once the program has errored.
+    fn dispatch<'lua, A: ToLuaMulti<'lua>>(&'lua self, name: &str, args: A) -> bool {
+        if self.has_error() { return false; }
+        match self.call_callback(name, args) {
+            Ok(called) => called,
+            Err(e) => {
+                self.report_error(&e);
+                true
+            }
+        }
     }
 
     pub fn on_resize(&self, window_size: (u32, u32)) {
@@ -43,28 +167,114 @@ impl LuaProgram {
         }
     }
 
+    /// Asks the program for whatever it wants to keep across a reload, through `husky.reload_state`.
+    /// Returns `None` if the program doesn't define it, or has already errored.
+    pub fn save_state(&self) -> LuaResult<Option<SavedState>> {
+        if self.has_error() { return Ok(None); }
+        match self.get_callback("reload_state")? {
+            Some(callback) => Ok(Some(callback.call::<_, SavedState>(())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Hands state saved by the previous program to `husky.restore_state`.
+    pub fn restore_state(&self, state: SavedState) {
+        self.dispatch("restore_state", state);
+    }
+
+    /// Runs any `husky.timer` callbacks that are due, before `husky.update`.
     pub fn update(&self, dt_s: f32) {
-        let globals = self.lua.globals();
-        if globals.contains_key("husky").expect("Somehow failed to check for key in table!") {
-            let api = globals.get::<&str, Table>("husky").unwrap();
-            if api.contains_key("update").expect("Somehow failed to check for key in table!") {
-                api.get::<&str, Function>("update").unwrap().call::<_, ()>(dt_s).expect("Failed to call update function!");
-            }
+        self.timer.borrow_mut().record(dt_s);
+        if self.has_error() { return; }
+
+        if let Err(e) = timer::run_scheduled(&self.lua, &self.timer, dt_s) {
+            self.report_error(&e);
+            return;
+        }
+        self.dispatch("update", dt_s);
+    }
+
+    /// Called zero or more times per frame when running with a fixed timestep.
+    pub fn fixedupdate(&self, dt_s: f32) {
+        self.dispatch("fixedupdate", dt_s);
+    }
+
+    /// `alpha` is how far along the next fixed step we are, from 0 to 1,
+    /// for interpolating between the last two fixed steps.
+    /// Does nothing when running headless.
+    pub fn draw(&self, alpha: f32) {
+        let renderer = match &self.renderer {
+            Some(renderer) => renderer,
+            None => return,
+        };
+
+        if let Some(message) = self.error.borrow().as_ref() {
+            error_screen::draw(renderer, message);
+            return;
+        }
+
+        renderer.get_lock().begin_frame();
+        let result = self.call_callback("draw", alpha);
+        if self.show_fps {
+            self.draw_fps_overlay(renderer);
         }
+        //Always finish the frame, so an error halfway through doesn't leave a shader bound
+        renderer.get_lock().finish_frame();
 
-        //Alternative
-        // let _ = self.lua.load("husky.update(0)").exec();
+        if let Err(e) = result {
+            self.report_error(&e);
+        }
+    }
+
+    fn draw_fps_overlay(&self, renderer: &RendererGuard) {
+        let text = format!("FPS: {}", self.timer.borrow().fps());
+        renderer.get_lock().print_overlay((1.0, 1.0, 0.0, 1.0), &text, 8.0, 8.0);
     }
 
-    pub fn draw(&self) {
-        let globals = self.lua.globals();
-        if globals.contains_key("husky").expect("Somehow failed to check for key in table!") {
-            let api = globals.get::<&str, Table>("husky").unwrap();
-            if api.contains_key("draw").expect("Somehow failed to check for key in table!") {
-                self.lua.load("husky.graphics:begin_frame()").exec().expect("Failed to call `husky.graphics.begin_frame`!");
-                api.get::<&str, Function>("draw").unwrap().call::<_, ()>(()).expect("Failed to call draw function!");
-                self.lua.load("husky.graphics:finish_frame()").exec().expect("Failed to call `husky.graphics.finish_frame`!");
+    /// Returns whether the program handled the key itself, by returning true from `husky.keypressed`.
+    /// If it didn't, the caller is free to apply its default behaviour.
+    pub fn keypressed(&self, key: &str, scancode: u32, is_repeat: bool) -> bool {
+        if self.has_error() {
+            if key == "r" {
+                self.reload_requested.set(true);
+                return true;
+            }
+            return false;
+        }
+        let result = self.get_callback("keypressed").and_then(|callback| match callback {
+            Some(callback) => Ok(callback.call::<_, Option<bool>>((key, scancode, is_repeat))?.unwrap_or(false)),
+            None => Ok(false),
+        });
+        match result {
+            Ok(handled) => handled,
+            Err(e) => {
+                self.report_error(&e);
+                true
             }
         }
     }
+
+    pub fn keyreleased(&self, key: &str, scancode: u32) {
+        self.dispatch("keyreleased", (key, scancode));
+    }
+
+    pub fn textinput(&self, text: &str) {
+        self.dispatch("textinput", text);
+    }
+
+    pub fn mousepressed(&self, x: f32, y: f32, button: u16) {
+        self.dispatch("mousepressed", (x, y, button));
+    }
+
+    pub fn mousereleased(&self, x: f32, y: f32, button: u16) {
+        self.dispatch("mousereleased", (x, y, button));
+    }
+
+    pub fn mousemoved(&self, x: f32, y: f32, dx: f32, dy: f32) {
+        self.dispatch("mousemoved", (x, y, dx, dy));
+    }
+
+    pub fn wheelmoved(&self, x: f32, y: f32) {
+        self.dispatch("wheelmoved", (x, y));
+    }
 }
@@ -0,0 +1,39 @@
+use mlua::{Table, Value};
+use mlua::prelude::*;
+
+use husky_fs::GameFs;
+
+/// The files `require` tries for a module, in order. Like Love2D,
+/// `require("a.b")` looks for `a/b.lua` first and `a/b/init.lua` second.
+fn module_paths(name: &str) -> [String; 2] {
+    let base = name.replace('.', "/");
+    [format!("{}.lua", base), format!("{}/init.lua", base)]
+}
+
+/// Installs a package searcher that resolves modules relative to the root of the game.
+/// It only goes through `GameFs`, so it works the same for folders and packaged games.
+pub fn install_searcher(lua: &Lua, fs: GameFs) -> LuaResult<()> {
+    let searcher = lua.create_function(move |lua, name: String| {
+        let paths = module_paths(&name);
+        for path in paths.iter() {
+            if fs.is_file(path) {
+                let source = fs.read(path)?;
+                let loader = lua.load(&source).set_name(&format!("@{}", path))?.into_function()?;
+                return Ok(Value::Function(loader));
+            }
+        }
+
+        //Searchers report what they tried, `require` glues these together in its error message
+        let tried: String = paths.iter().map(|path| format!("\n\tno file '{}' in game `{}`", path, fs.display())).collect();
+        Ok(Value::String(lua.create_string(&tried)?))
+    })?;
+
+    let package: Table = lua.globals().get("package")?;
+    //LuaJIT still calls these `loaders`, Lua 5.2 and up renamed them to `searchers`
+    let searchers: Table = match package.get::<_, Option<Table>>("searchers")? {
+        Some(searchers) => searchers,
+        None => package.get("loaders")?,
+    };
+    //Right after the preload searcher, so the game's modules win over the working directory
+    searchers.raw_insert(2, searcher)
+}
@@ -0,0 +1,69 @@
+use mlua::{FromLua, ToLua, Value};
+use mlua::prelude::*;
+
+/// Tables nested deeper than this are assumed to contain a cycle.
+const MAX_DEPTH: usize = 64;
+
+/// A copy of a Lua value that doesn't belong to any Lua state.
+/// This is what carries a program's state across a hot reload, from
+/// `husky.reload_state` in the old program to `husky.restore_state` in the new one.
+/// Only plain data survives: functions, userdata and threads belong to the old state.
+#[derive(Clone, Debug)]
+pub enum SavedState {
+    Nil,
+    Boolean(bool),
+    Integer(LuaInteger),
+    Number(LuaNumber),
+    String(Vec<u8>),
+    Table(Vec<(SavedState, SavedState)>),
+}
+
+impl SavedState {
+    fn from_value(value: Value, depth: usize) -> LuaResult<Self> {
+        if depth > MAX_DEPTH {
+            return Err(LuaError::RuntimeError("Reload state is nested too deep, does it contain a cycle?".to_string()));
+        }
+
+        Ok(match value {
+            Value::Nil => Self::Nil,
+            Value::Boolean(v) => Self::Boolean(v),
+            Value::Integer(v) => Self::Integer(v),
+            Value::Number(v) => Self::Number(v),
+            Value::String(v) => Self::String(v.as_bytes().to_vec()),
+            Value::Table(table) => {
+                let mut pairs = Vec::new();
+                for pair in table.pairs::<Value, Value>() {
+                    let (key, value) = pair?;
+                    pairs.push((Self::from_value(key, depth + 1)?, Self::from_value(value, depth + 1)?));
+                }
+                Self::Table(pairs)
+            },
+            other => return Err(LuaError::RuntimeError(format!("Can't carry a {} across a reload!", other.type_name()))),
+        })
+    }
+}
+
+impl<'lua> FromLua<'lua> for SavedState {
+    fn from_lua(value: Value<'lua>, _lua: &'lua Lua) -> LuaResult<Self> {
+        Self::from_value(value, 0)
+    }
+}
+
+impl<'lua> ToLua<'lua> for SavedState {
+    fn to_lua(self, lua: &'lua Lua) -> LuaResult<Value<'lua>> {
+        Ok(match self {
+            Self::Nil => Value::Nil,
+            Self::Boolean(v) => Value::Boolean(v),
+            Self::Integer(v) => Value::Integer(v),
+            Self::Number(v) => Value::Number(v),
+            Self::String(v) => Value::String(lua.create_string(&v)?),
+            Self::Table(pairs) => {
+                let table = lua.create_table()?;
+                for (key, value) in pairs {
+                    table.set(key, value)?;
+                }
+                Value::Table(table)
+            },
+        })
+    }
+}
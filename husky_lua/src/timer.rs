@@ -0,0 +1,186 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use mlua::{Function, RegistryKey, Table};
+use mlua::prelude::*;
+
+/// Deltas older than this don't count towards the FPS and average delta.
+const AVERAGE_WINDOW_S: f32 = 1.0;
+
+struct Scheduled {
+    id: u64,
+    remaining_s: f32,
+    ///`None` for `after`, which only fires once.
+    interval_s: Option<f32>,
+    callback: RegistryKey,
+}
+
+/// Frame timing and scheduled callbacks, shared between `husky.timer` and the program.
+pub struct Timer {
+    start: Instant,
+    last_step: Instant,
+    delta_s: f32,
+    ///Recent deltas, newest last, covering about `AVERAGE_WINDOW_S` seconds.
+    recent: VecDeque<f32>,
+
+    next_id: u64,
+    scheduled: Vec<Scheduled>,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            start: now,
+            last_step: now,
+            delta_s: 0.0,
+            recent: VecDeque::new(),
+
+            next_id: 1,
+            scheduled: Vec::new(),
+        }
+    }
+
+    /// Records a frame that took `delta_s` seconds.
+    pub fn record(&mut self, delta_s: f32) {
+        self.last_step = Instant::now();
+        self.delta_s = delta_s;
+        self.recent.push_back(delta_s);
+        while self.recent.iter().sum::<f32>() > AVERAGE_WINDOW_S && self.recent.len() > 1 {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Measures the time since the last frame or step, and makes it the delta.
+    /// It isn't added to the recent deltas, the frame it's part of already gets recorded as a whole.
+    fn step(&mut self) -> f32 {
+        let now = Instant::now();
+        self.delta_s = (now - self.last_step).as_secs_f32();
+        self.last_step = now;
+        self.delta_s
+    }
+
+    pub fn time(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    pub fn average_delta(&self) -> f32 {
+        if self.recent.is_empty() { return 0.0; }
+        self.recent.iter().sum::<f32>() / self.recent.len() as f32
+    }
+
+    pub fn fps(&self) -> u32 {
+        let average = self.average_delta();
+        if average > 0.0 { (1.0 / average).round() as u32 } else { 0 }
+    }
+
+    fn schedule(&mut self, delay_s: f32, interval_s: Option<f32>, callback: RegistryKey) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.scheduled.push(Scheduled {
+            id: id,
+            remaining_s: delay_s,
+            interval_s: interval_s,
+            callback: callback,
+        });
+        id
+    }
+
+    fn cancel(&mut self, lua: &Lua, id: u64) -> LuaResult<bool> {
+        match self.scheduled.iter().position(|s| s.id == id) {
+            Some(index) => {
+                let scheduled = self.scheduled.remove(index);
+                lua.remove_registry_value(scheduled.callback)?;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+}
+
+/// Counts down the scheduled callbacks and calls the ones that are due.
+/// The callbacks are free to schedule or cancel timers themselves, so the
+/// timer isn't borrowed while they run.
+pub fn run_scheduled(lua: &Lua, timer: &RefCell<Timer>, delta_s: f32) -> LuaResult<()> {
+    let mut due = Vec::new();
+    {
+        let mut timer = timer.borrow_mut();
+        for scheduled in timer.scheduled.iter_mut() {
+            scheduled.remaining_s -= delta_s;
+            if scheduled.remaining_s <= 0.0 {
+                due.push((scheduled.id, lua.registry_value::<Function>(&scheduled.callback)?));
+                if let Some(interval_s) = scheduled.interval_s {
+                    //Firing late pushes the next one back by the same amount, rather than firing twice in a row
+                    scheduled.remaining_s = (scheduled.remaining_s + interval_s).max(0.0);
+                }
+            }
+        }
+    }
+
+    for (id, callback) in due {
+        let is_repeating = match timer.borrow().scheduled.iter().find(|s| s.id == id) {
+            Some(scheduled) => scheduled.interval_s.is_some(),
+            //Cancelled by an earlier callback this frame
+            None => continue,
+        };
+        if !is_repeating {
+            timer.borrow_mut().cancel(lua, id)?;
+        }
+        callback.call::<_, ()>(())?;
+    }
+
+    Ok(())
+}
+
+fn check_delay(name: &str, seconds: f32) -> LuaResult<()> {
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(LuaError::RuntimeError(format!("husky.timer.{} needs a finite delay of 0 seconds or more!", name)));
+    }
+    Ok(())
+}
+
+/// Builds the `husky.timer` table. Unlike `husky.graphics`, these are called with a `.`
+pub fn create_interface<'lua>(lua: &'lua Lua, timer: Rc<RefCell<Timer>>) -> LuaResult<Table<'lua>> {
+    let table = lua.create_table()?;
+
+    let t = timer.clone();
+    table.set("getTime", lua.create_function(move |_, ()| Ok(t.borrow().time()))?)?;
+    let t = timer.clone();
+    table.set("getDelta", lua.create_function(move |_, ()| Ok(t.borrow().delta_s))?)?;
+    let t = timer.clone();
+    table.set("getFPS", lua.create_function(move |_, ()| Ok(t.borrow().fps()))?)?;
+    let t = timer.clone();
+    table.set("getAverageDelta", lua.create_function(move |_, ()| Ok(t.borrow().average_delta()))?)?;
+    let t = timer.clone();
+    table.set("step", lua.create_function(move |_, ()| Ok(t.borrow_mut().step()))?)?;
+
+    table.set("sleep", lua.create_function(|_, seconds: f32| {
+        check_delay("sleep", seconds)?;
+        //Finite delays can still be too long for a Duration
+        let duration = Duration::try_from_secs_f32(seconds).map_err(|_| LuaError::RuntimeError("husky.timer.sleep was given too long a delay!".to_string()))?;
+        std::thread::sleep(duration);
+        Ok(())
+    })?)?;
+
+    let t = timer.clone();
+    table.set("after", lua.create_function(move |lua, (seconds, callback): (f32, Function)| {
+        check_delay("after", seconds)?;
+        let key = lua.create_registry_value(callback)?;
+        Ok(t.borrow_mut().schedule(seconds, None, key))
+    })?)?;
+    let t = timer.clone();
+    table.set("every", lua.create_function(move |lua, (seconds, callback): (f32, Function)| {
+        check_delay("every", seconds)?;
+        if seconds == 0.0 {
+            return Err(LuaError::RuntimeError("husky.timer.every needs an interval above 0 seconds!".to_string()));
+        }
+        let key = lua.create_registry_value(callback)?;
+        Ok(t.borrow_mut().schedule(seconds, Some(seconds), key))
+    })?)?;
+    let t = timer;
+    table.set("cancel", lua.create_function(move |lua, id: u64| t.borrow_mut().cancel(lua, id))?)?;
+
+    Ok(table)
+}
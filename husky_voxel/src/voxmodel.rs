@@ -4,6 +4,8 @@ use mlua::{Result as LuaResult, Error as LuaError, UserData, UserDataMethods};
 
 use dot_vox::Model as VoxModelRaw;
 
+use husky_fs::GameFs;
+
 use crate::model::{Voxel, Brick, Model};
 
 #[derive(Clone)]
@@ -12,8 +14,9 @@ pub struct VoxModel {
 }
 
 impl VoxModel {
-    pub fn from_filename(path: &str) -> LuaResult<Self> {
-        let vox = dot_vox::load(path).or_else(|_| Err(LuaError::RuntimeError("Failed to find file!".into())) )?;
+    pub fn from_filename(fs: &GameFs, path: &str) -> LuaResult<Self> {
+        let bytes = fs.read(path).or_else(|_| Err(LuaError::RuntimeError("Failed to find file!".into())) )?;
+        let vox = dot_vox::load_bytes(&bytes).map_err(|e| LuaError::RuntimeError(format!("Failed to parse `{}`: {}", path, e)) )?;
         //TODO: When dot_vox updates to support scene graph loading, we should place models in the right spot
         //      Right now all models will be at 0,0,0
 
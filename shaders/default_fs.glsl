@@ -5,10 +5,13 @@ in VS_OUTPUT {
     vec2 UV;
 } IN;
 
-uniform vec4 drawColor;
+in float Alpha;
+
+//Untextured draws get a white texture bound
+uniform sampler2D tex;
 
 layout (location = 0) out vec4 Color;
 
 void main() {
-    Color = vec4(IN.Color, 1.0) * drawColor;
+    Color = texture(tex, IN.UV) * vec4(IN.Color, Alpha);
 }
@@ -11,9 +11,13 @@ out VS_OUTPUT {
     vec2 UV;
 } OUT;
 
+//Outside of VS_OUTPUT, so fragment shaders written before vertex colors had alpha still link
+out float Alpha;
+
 void main() {
     vec4 pos = mvp * vec4(Position, 1.0);
     gl_Position = pos;
     OUT.Color = Color.xyz;
     OUT.UV = UV;
+    Alpha = Color.a;
 }
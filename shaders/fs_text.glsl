@@ -1,23 +0,0 @@
-#version 450 core
-
-layout(location = 0) uniform sampler2D tex;
-
-in VS_OUTPUT {
-    vec3 Color;
-    vec2 UV;
-} IN;
-
-uniform vec4 drawColor;
-
-layout(location = 0) out vec4 Color;
-
-void main() {
-    vec4 data = texture(tex, IN.UV);
-    float alpha = data.a;
-    vec3 col = data.rgb * drawColor.rgb;
-    if (alpha <= 0.0) {
-        discard;
-    }
-    // Color = vec4(IN.Color, alpha);
-    Color = vec4(col, 1.0);
-}
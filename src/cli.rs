@@ -0,0 +1,151 @@
+use getopts::{Options, ParsingStyle};
+
+use log::LevelFilter;
+
+/// Everything needed to run a game, as passed on the command line.
+pub struct RunArgs {
+    /// Folder, `.husky` archive or file inside the game folder. Empty runs the default program.
+    pub game: String,
+    /// Everything after the game path, forwarded to Lua as `husky.args`.
+    pub game_args: Vec<String>,
+
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub show_fps: bool,
+    pub log_level: Option<LevelFilter>,
+    /// Steps per second for `husky.fixedupdate`.
+    pub fixed_timestep: Option<f32>,
+    /// Run without a window, and without `husky.graphics`.
+    pub headless: bool,
+}
+
+pub enum Command {
+    Run(RunArgs),
+    Pack(String),
+    Help(String),
+    Version,
+}
+
+fn options() -> Options {
+    let mut opts = Options::new();
+    //Anything after the game path belongs to the game
+    opts.parsing_style(ParsingStyle::StopAtFirstFree);
+    opts.optflag("h", "help", "prints this help menu");
+    opts.optflag("V", "version", "prints the version of husky");
+    opts.optopt("", "pack", "packs a game folder into a .husky archive next to it", "DIR");
+    opts.optopt("", "width", "width of the window", "PIXELS");
+    opts.optopt("", "height", "height of the window", "PIXELS");
+    opts.optflag("", "fullscreen", "starts in borderless fullscreen");
+    opts.optflag("", "vsync", "waits for vertical sync before showing a frame");
+    opts.optflag("", "show-fps", "draws the FPS in the corner of the window");
+    opts.optopt("", "log-level", "off, error, warn, info, debug or trace. Defaults to RUST_LOG, or info", "LEVEL");
+    opts.optopt("", "fixed-timestep", "calls husky.fixedupdate at this rate", "HZ");
+    opts.optflag("", "headless", "runs without a window, husky.graphics is not available");
+    opts
+}
+
+fn parse_opt<T: std::str::FromStr>(matches: &getopts::Matches, name: &str) -> Result<Option<T>, String> {
+    match matches.opt_str(name) {
+        Some(value) => value.parse().map(Some).map_err(|_| format!("Invalid value for --{}: `{}`", name, value)),
+        None => Ok(None),
+    }
+}
+
+pub fn usage() -> String {
+    options().usage("Usage: husky [options] [GAME] [GAME ARGS...]")
+}
+
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let matches = options().parse(args).map_err(|f| f.to_string())?;
+
+    if matches.opt_present("help") { return Ok(Command::Help(usage())); }
+    if matches.opt_present("version") { return Ok(Command::Version); }
+    if let Some(dir) = matches.opt_str("pack") { return Ok(Command::Pack(dir)); }
+
+    let fixed_timestep: Option<f32> = parse_opt(&matches, "fixed-timestep")?;
+    if fixed_timestep.is_some_and(|rate| rate.is_nan() || rate <= 0.0) {
+        return Err("--fixed-timestep must be above 0".to_string());
+    }
+
+    let mut free = matches.free.clone().into_iter();
+    Ok(Command::Run(RunArgs {
+        game: free.next().unwrap_or_default(),
+        game_args: free.collect(),
+
+        width: parse_opt(&matches, "width")?,
+        height: parse_opt(&matches, "height")?,
+        fullscreen: matches.opt_present("fullscreen"),
+        vsync: matches.opt_present("vsync"),
+        show_fps: matches.opt_present("show-fps"),
+        log_level: parse_opt(&matches, "log-level")?,
+        fixed_timestep,
+        headless: matches.opt_present("headless"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_strs(args: &[&str]) -> Result<Command, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse(&args)
+    }
+
+    fn run_args(args: &[&str]) -> RunArgs {
+        match parse_strs(args) {
+            Ok(Command::Run(run_args)) => run_args,
+            Ok(_) => panic!("{:?} didn't parse to a run command", args),
+            Err(e) => panic!("{:?} failed to parse: {}", args, e),
+        }
+    }
+
+    #[test]
+    fn no_args_runs_the_default_program() {
+        let run_args = run_args(&[]);
+        assert_eq!(run_args.game, "");
+        assert!(run_args.game_args.is_empty());
+        assert!(!run_args.headless && !run_args.fullscreen && !run_args.vsync && !run_args.show_fps);
+        assert_eq!(run_args.width, None);
+        assert_eq!(run_args.log_level, None);
+    }
+
+    #[test]
+    fn parses_options() {
+        let run_args = run_args(&["--width", "640", "--height", "480", "--vsync", "--show-fps", "--log-level", "debug", "--fixed-timestep", "60", "mygame"]);
+        assert_eq!(run_args.game, "mygame");
+        assert_eq!(run_args.width, Some(640));
+        assert_eq!(run_args.height, Some(480));
+        assert!(run_args.vsync && run_args.show_fps);
+        assert_eq!(run_args.log_level, Some(LevelFilter::Debug));
+        assert_eq!(run_args.fixed_timestep, Some(60.0));
+    }
+
+    #[test]
+    fn everything_after_the_game_belongs_to_the_game() {
+        let run_args = run_args(&["--headless", "mygame", "--width", "10", "level1"]);
+        assert!(run_args.headless);
+        assert_eq!(run_args.game, "mygame");
+        assert_eq!(run_args.game_args, vec!["--width", "10", "level1"]);
+        assert_eq!(run_args.width, None);
+    }
+
+    #[test]
+    fn other_commands() {
+        assert!(matches!(parse_strs(&["--help"]), Ok(Command::Help(_))));
+        assert!(matches!(parse_strs(&["-V"]), Ok(Command::Version)));
+        assert!(matches!(parse_strs(&["--pack", "mygame"]), Ok(Command::Pack(dir)) if dir == "mygame"));
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert!(parse_strs(&["--width", "wide"]).is_err());
+        assert!(parse_strs(&["--log-level", "loud"]).is_err());
+        assert!(parse_strs(&["--fixed-timestep", "0"]).is_err());
+        assert!(parse_strs(&["--fixed-timestep", "-30"]).is_err());
+        assert!(parse_strs(&["--fixed-timestep", "nan"]).is_err());
+        assert!(parse_strs(&["--unknown"]).is_err());
+    }
+}
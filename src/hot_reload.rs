@@ -0,0 +1,41 @@
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+use notify::{Watcher, RecommendedWatcher, RecursiveMode, DebouncedEvent};
+
+/// Watches the game directory, so we can reload the program whenever one of its Lua files changes.
+pub struct HotReloader {
+    //Stops watching when dropped
+    _watcher: RecommendedWatcher,
+    rx: Receiver<DebouncedEvent>,
+}
+
+impl HotReloader {
+    pub fn new(directory: &Path) -> notify::Result<Self> {
+        let (tx, rx) = channel();
+        let mut watcher = notify::watcher(tx, Duration::from_millis(200))?;
+        watcher.watch(directory, RecursiveMode::Recursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+        })
+    }
+
+    /// Returns true if any `.lua` file changed since the last time this was called.
+    pub fn lua_changed(&self) -> bool {
+        let mut changed = false;
+        for event in self.rx.try_iter() {
+            let path = match &event {
+                DebouncedEvent::Create(path) | DebouncedEvent::Write(path) | DebouncedEvent::Remove(path) => path,
+                DebouncedEvent::Rename(_, path) => path,
+                _ => continue,
+            };
+            if path.extension().is_some_and(|ext| ext == "lua") {
+                trace!("Lua file changed: {}", path.display());
+                changed = true;
+            }
+        }
+        changed
+    }
+}
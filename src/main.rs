@@ -1,22 +1,30 @@
 #[macro_use] extern crate log;
 
-use std::time::Instant;
-use std::fs::read_to_string;
+use std::time::Duration;
 use std::path::Path;
-use std::env;
+use std::{env, thread};
 
-use getopts::Options;
+use log::LevelFilter;
 
 use glutin::ContextBuilder;
 use glutin::dpi::LogicalSize;
 use glutin::event_loop::{EventLoop, ControlFlow};
-use glutin::event::{Event, WindowEvent, ElementState, KeyboardInput, VirtualKeyCode};
-use glutin::window::WindowBuilder;
+use glutin::event::{Event, WindowEvent, ElementState, KeyboardInput, VirtualKeyCode, MouseScrollDelta};
+use glutin::window::{WindowBuilder, Fullscreen};
 use glutin::{GlProfile, GlRequest, Api};
 
-use husky_lua::LuaProgram;
+use husky_lua::{LuaProgram, ProgramOptions, GameConfig, TimestepConfig};
+use husky_input::{keys, INPUT_STATE};
+use husky_fs::GameFs;
 
-static DEFAULT_WINDOW_SIZE: (u32, u32) = (1280u32, 720u32);
+mod cli;
+use cli::Command;
+
+mod hot_reload;
+use hot_reload::HotReloader;
+
+mod timestep;
+use timestep::Timestep;
 
 static DEFAULT_PROG_SRC: &'static str = include_str!("../default_main.lua");
 
@@ -24,64 +32,226 @@ fn load_gl(gl_context: &glutin::Context<glutin::PossiblyCurrent>) {
     gl::load_with(|ptr| gl_context.get_proc_address(ptr) as *const _);
 }
 
+fn load_program(game_fs: &GameFs, options: &ProgramOptions) -> LuaProgram {
+    debug!("Trying to load program from `{}`", game_fs.display());
+    let source = game_fs.read_to_string("main.lua").unwrap_or(DEFAULT_PROG_SRC.to_string());
+    program_from_source(game_fs, &source, options)
+}
+
+fn program_from_source(game_fs: &GameFs, source: &str, options: &ProgramOptions) -> LuaProgram {
+    LuaProgram::from_source(game_fs.clone(), source, options).expect("Failed to get program!")
+}
+
+/// Packs a game folder into `<folder>.husky`, next to the folder.
+fn pack_game(directory: &Path) -> std::io::Result<()> {
+    let output = directory.canonicalize()?.with_extension(husky_fs::ARCHIVE_EXTENSION);
+    let count = husky_fs::pack_directory(directory, &output)?;
+    info!("Packed {} files into `{}`", count, output.display());
+    Ok(())
+}
+
+/// Swaps in a freshly loaded program, carrying state over through `husky.reload_state`
+/// and `husky.restore_state`. If the new program fails, the old one keeps running.
+fn hot_reload(program: &mut LuaProgram, game_fs: &GameFs, options: &ProgramOptions) {
+    //Editors can briefly leave main.lua missing or empty while saving, that's not a reason to swap in the default program.
+    //Finishing the save changes the file again, which reloads for real
+    let source = match game_fs.read_to_string("main.lua") {
+        Ok(source) if !source.trim().is_empty() => source,
+        Ok(_) => {
+            warn!("main.lua is empty, keeping the current program running");
+            return;
+        },
+        Err(e) => {
+            warn!("Failed to read main.lua, keeping the current program running: {}", e);
+            return;
+        }
+    };
+
+    let state = match program.save_state() {
+        Ok(state) => state,
+        Err(e) => {
+            error!("Failed to save state for reload, not reloading: {}", e);
+            return;
+        }
+    };
+
+    let new_program = program_from_source(game_fs, &source, options);
+    if let Some(state) = state {
+        new_program.restore_state(state);
+    }
+
+    if new_program.has_error() && !program.has_error() {
+        error!("Reload failed, keeping the previous program running");
+        return;
+    }
+    *program = new_program;
+}
+
+/// Runs the program without a window, until it quits or hits an error.
+fn run_headless(game_fs: &GameFs, options: &ProgramOptions, timestep_config: &TimestepConfig) {
+    let program = load_program(game_fs, options);
+    let mut timestep = Timestep::new(timestep_config);
+
+    while !program.has_error() && !program.quit_requested() {
+        let delta_s = timestep.advance(|dt| program.fixedupdate(dt));
+        program.update(delta_s);
+        //Nothing to wait on without a window, so don't spin the CPU at 100%
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    if program.has_error() {
+        std::process::exit(1);
+    }
+}
+
+fn init_logger(level: Option<LevelFilter>) {
+    let mut builder = pretty_env_logger::formatted_builder();
+    match (level, env::var("RUST_LOG")) {
+        (Some(level), _) => builder.filter_level(level),
+        (None, Ok(filters)) => builder.parse_filters(&filters),
+        (None, Err(_)) => builder.filter_level(LevelFilter::Info),
+    };
+    builder.init();
+}
+
+fn open_game(path_input: &str) -> GameFs {
+    let p = Path::new(path_input);
+    if GameFs::is_archive_path(p) {
+        return match GameFs::from_archive(p) {
+            Ok(game_fs) => game_fs,
+            Err(e) => {
+                error!("Failed to open archive `{}`: {}", p.display(), e);
+                std::process::exit(1);
+            }
+        };
+    }
+
+    let mut buf = p.to_path_buf();
+    if !p.exists() {
+        if !path_input.is_empty() && path_input != "." { error!("Directory passed does not exist!"); }
+        buf = Path::new("").to_path_buf()
+    }
+    if !p.is_dir() {
+        buf.pop();
+    }
+    GameFs::from_directory(buf)
+}
+
+/// Reads `conf.lua`, with anything passed on the command line taking priority.
+/// A broken `conf.lua` isn't fatal, the game just starts with the defaults.
+fn load_config(game_fs: &GameFs, run_args: &cli::RunArgs) -> GameConfig {
+    let mut config = GameConfig::load(game_fs).unwrap_or_else(|e| {
+        error!("Failed to load conf.lua, using the default configuration: {}", e);
+        GameConfig::default()
+    });
+
+    if let Some(width) = run_args.width { config.window.width = width; }
+    if let Some(height) = run_args.height { config.window.height = height; }
+    if run_args.fullscreen { config.window.fullscreen = true; }
+    if run_args.vsync { config.window.vsync = true; }
+    if run_args.show_fps { config.window.show_fps = true; }
+    if run_args.fixed_timestep.is_some() { config.timestep.fixed_rate = run_args.fixed_timestep; }
+
+    config
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let path_input = if args.len() > 1 { args[1].clone() } else { "".to_string() };
-    let mut opts = Options::new();
-    opts.optflag("h", "help", "prints this help menu");
-    let empty = Vec::new();
-    let opts_passed = if args.len() > 2 { &args[2..] } else { &empty[..] };
-    //TODO: Do something with this to create a proper CLI
-    let matches = match opts.parse(opts_passed) {
-        Ok(m) => m,
-        Err(f) => panic!("{}", f)
+    let run_args = match cli::parse(&args[1..]) {
+        Ok(Command::Run(run_args)) => run_args,
+        Ok(Command::Help(usage)) => {
+            print!("{}", usage);
+            return;
+        },
+        Ok(Command::Version) => {
+            println!("husky {}", env!("CARGO_PKG_VERSION"));
+            return;
+        },
+        Ok(Command::Pack(pack_dir)) => {
+            init_logger(None);
+            if let Err(e) = pack_game(Path::new(&pack_dir)) {
+                error!("Failed to pack `{}`: {}", pack_dir, e);
+                std::process::exit(1);
+            }
+            return;
+        },
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::usage());
+            std::process::exit(1);
+        },
     };
 
-    let max_level = log::LevelFilter::max();
-    pretty_env_logger::formatted_builder()
-        .filter_level(max_level)
-        .init();
+    init_logger(run_args.log_level);
 
     debug!("Hello, world!");
 
-    let directory = {
-        let p = Path::new(&path_input);
-        let mut buf = p.to_path_buf();
-        if !p.exists() {
-            if path_input != "" && path_input != "." { error!("Directory passed does not exist!"); }
-            buf = Path::new("").to_path_buf()
-        }
-        if p.is_dir() {
-            buf
-        } else {
-            buf.pop();
-            buf
-        }
+    let game_fs = open_game(&run_args.game);
+    let config = load_config(&game_fs, &run_args);
+    let options = ProgramOptions {
+        args: run_args.game_args.clone(),
+        headless: run_args.headless,
+        modules: config.modules.clone(),
+        show_fps: config.window.show_fps,
     };
 
+    if run_args.headless {
+        run_headless(&game_fs, &options, &config.timestep);
+        return;
+    }
+
+    let window_config = &config.window;
     let event_loop = EventLoop::new();
-    let logical_window_size: LogicalSize<u32> = DEFAULT_WINDOW_SIZE.into();
+    let logical_window_size: LogicalSize<u32> = (window_config.width, window_config.height).into();
 
-    let window_builder = WindowBuilder::new()
-        .with_title("Husky v0.0.1")
-        .with_inner_size(logical_window_size);
+    let mut window_builder = WindowBuilder::new()
+        .with_title(window_config.title.as_str())
+        .with_inner_size(logical_window_size)
+        .with_resizable(window_config.resizable);
+    if window_config.min_width.is_some() || window_config.min_height.is_some() {
+        let min_size: LogicalSize<u32> = (window_config.min_width.unwrap_or(1), window_config.min_height.unwrap_or(1)).into();
+        window_builder = window_builder.with_min_inner_size(min_size);
+    }
+    if window_config.fullscreen {
+        window_builder = window_builder.with_fullscreen(Some(Fullscreen::Borderless(None)));
+    }
 
-    let context = ContextBuilder::new().with_vsync(false).with_gl(GlRequest::Specific(Api::OpenGl, (4,5))).with_gl_profile(GlProfile::Core).build_windowed(window_builder, &event_loop).expect("Failed to create opengl context!");
+    let context = ContextBuilder::new()
+        .with_vsync(window_config.vsync)
+        .with_multisampling(window_config.msaa)
+        .with_depth_buffer(24)
+        .with_stencil_buffer(8)
+        .with_gl(GlRequest::Specific(Api::OpenGl, (4,5)))
+        .with_gl_profile(GlProfile::Core)
+        .build_windowed(window_builder, &event_loop)
+        .expect("Failed to create opengl context!");
     let context = unsafe { context.make_current().expect("Failed to make context current!") };
 
-    load_gl(&context.context());
+    load_gl(context.context());
 
-    let mut old_frametime = Instant::now();
+    let mut timestep = Timestep::new(&config.timestep);
     let mut close_requested = false;
 
     //Load program
-    let path = directory.join(Path::new("main.lua"));
-    debug!("Trying to load program from path `{}`", path.display());
-    let source = read_to_string(path).unwrap_or(DEFAULT_PROG_SRC.to_string());
-    let program = LuaProgram::from_source(directory.display().to_string(), &source).expect("Failed to get program!");
+    let mut program = load_program(&game_fs, &options);
     let mut dimensions: (u32, u32) = context.window().inner_size().into();
     program.on_resize(dimensions);
 
+    //Only watch folders that actually hold a game, watching the whole working directory could be huge.
+    //Packaged games can't change underneath us, so there's nothing to watch for those.
+    let reloader = match game_fs.directory() {
+        Some(directory) if directory.join("main.lua").exists() => {
+            let watch_dir = if directory.as_os_str().is_empty() { Path::new(".") } else { directory };
+            match HotReloader::new(watch_dir) {
+                Ok(reloader) => Some(reloader),
+                Err(e) => {
+                    warn!("Failed to watch `{}` for changes, hot reloading is disabled: {}", watch_dir.display(), e);
+                    None
+                }
+            }
+        },
+        _ => None,
+    };
+
     event_loop.run(move |event, _, control_flow| {
         match event {
             Event::WindowEvent { event, .. } => match event {
@@ -92,39 +262,97 @@ fn main() {
                     input:
                         KeyboardInput {
                             virtual_keycode: Some(virtual_code),
-                            state: ElementState::Pressed,
+                            scancode,
+                            state,
                             ..
                         },
                     ..
-                } => match virtual_code {
-                    VirtualKeyCode::Escape => {
-                        close_requested = true;
+                } => {
+                    let key = keys::key_to_str(virtual_code);
+                    match state {
+                        ElementState::Pressed => {
+                            let is_repeat = INPUT_STATE.lock().unwrap().press_key(virtual_code, scancode);
+                            let handled = program.keypressed(key, scancode, is_repeat);
+                            //Escape quits, unless `husky.keypressed` returned true to say it handled the key
+                            if !handled && virtual_code == VirtualKeyCode::Escape {
+                                close_requested = true;
+                            }
+                        },
+                        ElementState::Released => {
+                            INPUT_STATE.lock().unwrap().release_key(virtual_code);
+                            program.keyreleased(key, scancode);
+                        },
+                    }
+                },
+                WindowEvent::ReceivedCharacter(c) if !c.is_control() => {
+                    program.textinput(&c.to_string());
+                },
+                WindowEvent::CursorMoved { position, .. } => {
+                    let pos = (position.x as f32, position.y as f32);
+                    let delta = INPUT_STATE.lock().unwrap().move_mouse(pos);
+                    program.mousemoved(pos.0, pos.1, delta.0, delta.1);
+                },
+                WindowEvent::MouseInput { state, button, .. } => {
+                    let button = keys::button_to_index(button);
+                    let pos = INPUT_STATE.lock().unwrap().mouse_pos();
+                    match state {
+                        ElementState::Pressed => {
+                            INPUT_STATE.lock().unwrap().press_button(button);
+                            program.mousepressed(pos.0, pos.1, button);
+                        },
+                        ElementState::Released => {
+                            INPUT_STATE.lock().unwrap().release_button(button);
+                            program.mousereleased(pos.0, pos.1, button);
+                        },
+                    }
+                },
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let (x, y) = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => (x, y),
+                        MouseScrollDelta::PixelDelta(pos) => (pos.x as f32, pos.y as f32),
+                    };
+                    program.wheelmoved(x, y);
+                },
+                WindowEvent::Focused(false) => {
+                    //Tell the program too, or anything it tracks through the callbacks stays held down
+                    let (released_keys, released_buttons) = INPUT_STATE.lock().unwrap().release_all();
+                    let pos = INPUT_STATE.lock().unwrap().mouse_pos();
+                    for (key, scancode) in released_keys {
+                        program.keyreleased(keys::key_to_str(key), scancode);
+                    }
+                    for button in released_buttons {
+                        program.mousereleased(pos.0, pos.1, button);
                     }
-                    _ => (),
                 },
                 _ => (),
             },
             Event::MainEventsCleared => {
-                if close_requested {
+                if program.reload_requested() {
+                    info!("Reloading program");
+                    program = load_program(&game_fs, &options);
+                    program.on_resize(dimensions);
+                } else if reloader.as_ref().is_some_and(|r| r.lua_changed()) {
+                    info!("Source changed, hot reloading program");
+                    hot_reload(&mut program, &game_fs, &options);
+                    program.on_resize(dimensions);
+                }
+
+                if close_requested || program.quit_requested() {
                     *control_flow = ControlFlow::Exit;
                 } else {
                     context.window().request_redraw();
                 }
             },
             Event::RedrawRequested(..) => {
-                let delta = Instant::now() - old_frametime;
-                old_frametime = Instant::now();
-                let delta_s = delta.as_secs() as f32 + (delta.subsec_micros() as f32 / 1_000_000f32);
-
                 if dimensions != context.window().inner_size().into() {
                     dimensions = context.window().inner_size().into();
                     program.on_resize(dimensions);
                 }
 
+                let delta_s = timestep.advance(|dt| program.fixedupdate(dt));
                 program.update(delta_s);
-                program.draw();
-
-                context.window().set_title(&format!("FPS: {}", 1.0 / delta_s));
+                program.draw(timestep.alpha());
+                INPUT_STATE.lock().unwrap().end_frame();
 
                 context.swap_buffers().expect("Failed to swap buffers!");
             },
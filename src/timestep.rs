@@ -0,0 +1,114 @@
+use std::time::Instant;
+
+use husky_lua::TimestepConfig;
+
+/// Keeps track of frame times, and hands out fixed steps when the program wants them.
+/// Frame times are added up and handed out in steps of exactly the same length,
+/// so anything simulated in those steps doesn't depend on the frame rate.
+pub struct Timestep {
+    fixed_dt: Option<f32>,
+    max_steps: u32,
+    accumulator: f32,
+    last_frame: Instant,
+}
+
+impl Timestep {
+    pub fn new(config: &TimestepConfig) -> Self {
+        Self {
+            fixed_dt: config.fixed_rate.map(|rate| 1.0 / rate),
+            max_steps: config.max_steps,
+            accumulator: 0.0,
+            last_frame: Instant::now(),
+        }
+    }
+
+    /// Measures the time since the last frame, and calls `fixed_step` for every fixed step that fits in it.
+    /// Returns the real time the frame took, in seconds.
+    pub fn advance<F: FnMut(f32)>(&mut self, fixed_step: F) -> f32 {
+        let now = Instant::now();
+        let delta_s = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        self.advance_by(delta_s, fixed_step);
+        delta_s
+    }
+
+    /// Calls `fixed_step` for every fixed step that fits in `delta_s` and what was left over from earlier frames.
+    fn advance_by<F: FnMut(f32)>(&mut self, delta_s: f32, mut fixed_step: F) {
+        if let Some(fixed_dt) = self.fixed_dt {
+            self.accumulator += delta_s;
+
+            let mut steps = 0;
+            while self.accumulator >= fixed_dt && steps < self.max_steps {
+                fixed_step(fixed_dt);
+                self.accumulator -= fixed_dt;
+                steps += 1;
+            }
+
+            //If the steps can't keep up, trying to catch up only makes the next frame slower.
+            //Drop the time we're behind instead, so the game slows down rather than locking up.
+            if self.accumulator >= fixed_dt {
+                debug!("Fixed steps can't keep up, skipping {} seconds", self.accumulator - self.accumulator % fixed_dt);
+                self.accumulator %= fixed_dt;
+            }
+        }
+    }
+
+    /// How far along the next fixed step we are, from 0 to 1.
+    /// Always 1 without fixed steps, as there's nothing to interpolate.
+    pub fn alpha(&self) -> f32 {
+        match self.fixed_dt {
+            Some(fixed_dt) => self.accumulator / fixed_dt,
+            None => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestep(rate: Option<f32>, max: u32) -> Timestep {
+        Timestep::new(&TimestepConfig {
+            fixed_rate: rate,
+            max_steps: max,
+        })
+    }
+
+    /// Advances by `delta_s`, returning the length of every fixed step that ran.
+    fn steps(timestep: &mut Timestep, delta_s: f32) -> Vec<f32> {
+        let mut steps = Vec::new();
+        timestep.advance_by(delta_s, |dt| steps.push(dt));
+        steps
+    }
+
+    #[test]
+    fn no_fixed_steps_without_a_rate() {
+        let mut timestep = timestep(None, 5);
+        assert!(steps(&mut timestep, 1.0).is_empty());
+        assert_eq!(timestep.alpha(), 1.0);
+    }
+
+    #[test]
+    fn leftover_time_carries_over() {
+        let mut timestep = timestep(Some(4.0), 5);
+        assert_eq!(steps(&mut timestep, 0.5), vec![0.25, 0.25]);
+        assert_eq!(timestep.alpha(), 0.0);
+
+        assert!(steps(&mut timestep, 0.125).is_empty());
+        assert_eq!(timestep.alpha(), 0.5);
+
+        assert_eq!(steps(&mut timestep, 0.125), vec![0.25]);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn time_past_max_steps_is_dropped() {
+        let mut timestep = timestep(Some(4.0), 2);
+        assert_eq!(steps(&mut timestep, 2.125), vec![0.25, 0.25]);
+        //Only the part of a step that was left over stays
+        assert_eq!(timestep.alpha(), 0.5);
+
+        assert_eq!(steps(&mut timestep, 0.125), vec![0.25]);
+    }
+}